@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+extern crate mobi;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(m) = mobi::Mobi::new(Vec::from(data)) {
+        let _ = m.content_as_string_lossy();
+        let _ = m.image_records();
+        let _ = m.raw_records();
+    }
+});
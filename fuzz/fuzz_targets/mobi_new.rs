@@ -3,5 +3,7 @@ use libfuzzer_sys::fuzz_target;
 extern crate mobi;
 
 fuzz_target!(|data: &[u8]| {
-    let _ = mobi::Mobi::new(Vec::from(data));
+    if let Ok(m) = mobi::Mobi::new(Vec::from(data)) {
+        let _ = m.try_content();
+    }
 });
@@ -4,6 +4,9 @@ use thiserror::Error;
 
 type HuffmanResult<T> = Result<T, HuffmanError>;
 
+/// Maximum depth of nested dictionary entries resolved while unpacking.
+const MAX_UNPACK_DEPTH: usize = 64;
+
 #[derive(Debug, Error)]
 pub enum HuffmanError {
     #[error(transparent)]
@@ -18,6 +21,10 @@ pub enum HuffmanError {
     InvalidCDICHeader,
     #[error("the provided index to huffman dictionary was out of bounds")]
     InvalidDictionaryIndex,
+    #[error("min and max codes in HUFF record are not monotonic")]
+    InvalidMinMaxCodes,
+    #[error("HUFF or CDIC record referenced by MOBI header doesn't exist")]
+    MissingHuffRecord,
 }
 
 type HuffmanDictionary = Vec<Option<(Vec<u8>, bool)>>;
@@ -79,7 +86,13 @@ impl HuffmanDecoder {
         for code_len in 1..=32 {
             self.min_codes[code_len] = reader.read_u32_be()? << (32 - code_len);
             self.max_codes[code_len] =
-                ((reader.read_u32_be()? + 1) << (32 - code_len)).saturating_sub(1);
+                (reader.read_u32_be()?.wrapping_add(1) << (32 - code_len)).wrapping_sub(1);
+        }
+
+        // Longer codes always map to lower values, otherwise codes can't be decoded.
+        let is_monotonic = |codes: &[u32]| codes.windows(2).all(|w| w[0] >= w[1]);
+        if !is_monotonic(&self.min_codes[1..]) || !is_monotonic(&self.max_codes[1..]) {
+            return Err(HuffmanError::InvalidMinMaxCodes);
         }
         Ok(())
     }
@@ -112,7 +125,15 @@ impl HuffmanDecoder {
         let num_phrases = r.read_u32_be()?;
         let bits = r.read_u32_be()?;
 
-        let n = (1 << bits).min(num_phrases - self.dictionary.len() as u32);
+        let remaining_phrases = num_phrases.saturating_sub(self.dictionary.len() as u32);
+        let n = 1u32
+            .checked_shl(bits)
+            .unwrap_or(u32::MAX)
+            .min(remaining_phrases);
+        // Each phrase needs at least a 2 byte offset, so the record can't possibly hold them all
+        if n == 0 || 16 + 2 * n as usize > cdic.len() {
+            return Err(HuffmanError::InvalidCDICHeader);
+        }
 
         let mut offsets = Vec::with_capacity(n as usize);
         for _ in 0..n {
@@ -120,6 +141,9 @@ impl HuffmanDecoder {
         }
 
         for offset in offsets {
+            if 16 + offset as usize + 2 > cdic.len() {
+                return Err(HuffmanError::InvalidCDICHeader);
+            }
             r.set_position(16 + offset as usize)?;
             let num_bytes = r.read_u16_be()?;
             let bytes = r.read_vec_header((num_bytes & 0x7FFF) as usize)?;
@@ -131,6 +155,9 @@ impl HuffmanDecoder {
     }
 
     fn load_cdic_records(&mut self, records: &[&[u8]]) -> HuffmanResult<()> {
+        if records.is_empty() {
+            return Err(HuffmanError::MissingHuffRecord);
+        }
         for cdic in records {
             self.load_cdic_record(cdic)?;
         }
@@ -138,11 +165,21 @@ impl HuffmanDecoder {
     }
 
     // Unpacks data of a section (?)
-    fn unpack(&mut self, data: &[u8]) -> HuffmanResult<Vec<u8>> {
+    fn unpack(&mut self, data: &[u8], depth: usize) -> HuffmanResult<Vec<u8>> {
+        // Dictionary entries can reference other entries, a crafted dictionary
+        // could make this recurse for too long.
+        if depth > MAX_UNPACK_DEPTH {
+            return Err(HuffmanError::InvalidDictionaryIndex);
+        }
+
         // Need len.
         let mut bits_left = data.len() * 8;
 
-        let mut r = Reader::new(std::io::Cursor::new(&data));
+        // Pad data with zeros so that the sliding window can always be refilled.
+        let mut padded = Vec::with_capacity(data.len() + 8);
+        padded.extend_from_slice(data);
+        padded.extend_from_slice(&[0; 8]);
+        let mut r = Reader::new(std::io::Cursor::new(padded));
 
         // X is a sliding window of 64 bits from data.
         let mut x = r.read_u64_be()?;
@@ -153,17 +190,7 @@ impl HuffmanDecoder {
         loop {
             // The top 32 bits are now stale, read next 32 bits.
             if n <= 0 {
-                // Can not read another 32 bits.
-                if bits_left < 32 {
-                    // Can read up to 3 bytes.
-                    for _ in 0..bits_left / 8 {
-                        x = (x << 8) | u64::from(r.read_u8()?);
-                    }
-                    // Pad last bits with 0.
-                    x <<= 32 - bits_left;
-                } else {
-                    x = (x << 32) | u64::from(r.read_u32_be()?);
-                }
+                x = (x << 32) | u64::from(r.read_u32_be()?);
                 n += 32;
             }
 
@@ -175,23 +202,17 @@ impl HuffmanDecoder {
             // 32 > code_len > 0.
             let mut code_len = code_len as usize;
             if !term {
-                // Last min_code is guaranteed to be 0, so no unwrap.
                 code_len += self.min_codes[code_len..]
                     .iter()
                     .position(|&min_code| code >= min_code)
-                    .unwrap();
+                    .ok_or(HuffmanError::CodeLenOutOfBounds)?;
                 max_code = self.max_codes[code_len];
             }
 
-            let index = ((max_code - code) >> (32 - code_len)) as usize;
-            println!(
-                "max_code: {}, code: {}, code_len: {}, index: {}, dict_len: {}",
-                max_code,
-                code,
-                code_len,
-                index,
-                self.dictionary.len()
-            );
+            let index = (max_code
+                .checked_sub(code)
+                .ok_or(HuffmanError::InvalidDictionaryIndex)?
+                >> (32 - code_len)) as usize;
             let (mut slice, flag) = std::mem::take(
                 self.dictionary
                     .get_mut(index)
@@ -199,7 +220,7 @@ impl HuffmanDecoder {
             )
             .ok_or(HuffmanError::InvalidDictionaryIndex)?;
             if !flag {
-                slice = self.unpack(&slice)?;
+                slice = self.unpack(&slice, depth + 1)?;
             }
             unpacked.extend_from_slice(&slice);
             self.dictionary[index] = Some((slice, true));
@@ -213,24 +234,22 @@ impl HuffmanDecoder {
             };
         }
 
-        println!("unpacked: {:?}", unpacked);
-
         Ok(unpacked)
     }
 
     fn unpack_sections(&mut self, sections: &[&[u8]]) -> HuffmanResult<Vec<Vec<u8>>> {
         let mut output = vec![];
         for section in sections {
-            output.push(self.unpack(section)?);
+            output.push(self.unpack(section, 0)?);
         }
         Ok(output)
     }
 
     fn init(huffs: &[&[u8]]) -> HuffmanResult<Self> {
+        let (huff, cdics) = huffs.split_first().ok_or(HuffmanError::MissingHuffRecord)?;
         let mut decoder = Self::default();
-        decoder.load_huff(huffs[0])?;
-        decoder.load_cdic_records(&huffs[1..])?;
-        eprintln!("{:#?}", decoder);
+        decoder.load_huff(huff)?;
+        decoder.load_cdic_records(cdics)?;
         Ok(decoder)
     }
 }
@@ -239,3 +258,178 @@ pub fn decompress(huffs: &[&[u8]], sections: &[&[u8]]) -> HuffmanResult<Vec<Vec<
     let mut decoder = HuffmanDecoder::init(huffs)?;
     decoder.unpack_sections(sections)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn init_no_huff_records() {
+        assert!(matches!(
+            HuffmanDecoder::init(&[]),
+            Err(HuffmanError::MissingHuffRecord)
+        ));
+        assert!(matches!(
+            decompress(&[], &[b"text"]),
+            Err(HuffmanError::MissingHuffRecord)
+        ));
+    }
+
+    fn cdic_record(num_phrases: u32, bits: u32, phrases: &[&[u8]]) -> Vec<u8> {
+        let mut cdic = b"CDIC".to_vec();
+        cdic.extend_from_slice(&0x10u32.to_be_bytes());
+        cdic.extend_from_slice(&num_phrases.to_be_bytes());
+        cdic.extend_from_slice(&bits.to_be_bytes());
+
+        let mut offset = 2 * phrases.len();
+        let mut data = vec![];
+        for phrase in phrases {
+            cdic.extend_from_slice(&(offset as u16).to_be_bytes());
+            data.extend_from_slice(&(0x8000 | phrase.len() as u16).to_be_bytes());
+            data.extend_from_slice(phrase);
+            offset += 2 + phrase.len();
+        }
+        cdic.extend_from_slice(&data);
+        cdic
+    }
+
+    #[test]
+    fn load_cdic_record() {
+        let mut decoder = HuffmanDecoder::default();
+        decoder
+            .load_cdic_record(&cdic_record(2, 1, &[b"a", b"b"]))
+            .unwrap();
+        assert_eq!(
+            decoder.dictionary,
+            vec![Some((b"a".to_vec(), true)), Some((b"b".to_vec(), true))]
+        );
+    }
+
+    #[test]
+    fn load_cdic_record_less_phrases() {
+        let mut decoder = HuffmanDecoder::default();
+        let records = [cdic_record(2, 1, &[b"a", b"b"]), cdic_record(1, 1, &[b"c"])];
+        let records: Vec<_> = records.iter().map(|r| r.as_slice()).collect();
+        assert!(matches!(
+            decoder.load_cdic_records(&records),
+            Err(HuffmanError::InvalidCDICHeader)
+        ));
+    }
+
+    #[test]
+    fn load_cdic_record_offset_out_of_bounds() {
+        let mut decoder = HuffmanDecoder::default();
+        let mut cdic = cdic_record(1, 1, &[b"a"]);
+        cdic[16..18].copy_from_slice(&1000u16.to_be_bytes());
+        assert!(matches!(
+            decoder.load_cdic_record(&cdic),
+            Err(HuffmanError::InvalidCDICHeader)
+        ));
+    }
+
+    fn huff_record(min_max_codes: &[(u32, u32); 32]) -> Vec<u8> {
+        let mut huff = b"HUFF".to_vec();
+        huff.extend_from_slice(&0x18u32.to_be_bytes());
+        // cache offset
+        huff.extend_from_slice(&0x18u32.to_be_bytes());
+        // base offset
+        huff.extend_from_slice(&(0x18u32 + 256 * 4).to_be_bytes());
+        huff.extend_from_slice(&[0; 8]);
+        for _ in 0..256 {
+            huff.extend_from_slice(&(0x80u32 | 8).to_be_bytes());
+        }
+        for (min_code, max_code) in min_max_codes {
+            huff.extend_from_slice(&min_code.to_be_bytes());
+            huff.extend_from_slice(&max_code.to_be_bytes());
+        }
+        huff
+    }
+
+    #[test]
+    fn load_huff() {
+        let mut decoder = HuffmanDecoder::default();
+        assert!(decoder.load_huff(&huff_record(&[(0, 0); 32])).is_ok());
+    }
+
+    #[test]
+    fn load_huff_scrambled_codes() {
+        let mut codes = [(0, 0); 32];
+        codes[4] = (31, 31);
+        let mut decoder = HuffmanDecoder::default();
+        assert!(matches!(
+            decoder.load_huff(&huff_record(&codes)),
+            Err(HuffmanError::InvalidMinMaxCodes)
+        ));
+
+        let mut codes = [(0, 0); 32];
+        codes[31] = (0, u32::MAX);
+        let mut decoder = HuffmanDecoder::default();
+        assert!(matches!(
+            decoder.load_huff(&huff_record(&codes)),
+            Err(HuffmanError::InvalidMinMaxCodes)
+        ));
+    }
+
+    /// Decoder where each byte of packed data is a code of an entry at `0xFF - byte` index.
+    fn byte_decoder(dictionary: HuffmanDictionary) -> HuffmanDecoder {
+        HuffmanDecoder {
+            dictionary,
+            code_dict: [(8, true, u32::MAX); 256],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn unpack() {
+        let mut decoder = byte_decoder(vec![
+            Some((b"ab".to_vec(), true)),
+            Some((vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], false)),
+        ]);
+        assert_eq!(
+            decoder
+                .unpack(&[0xFF, 0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF], 0)
+                .unwrap(),
+            b"ab".repeat(15)
+        );
+    }
+
+    #[test]
+    fn unpack_self_referencing_entry() {
+        let mut decoder = byte_decoder(vec![Some((vec![0xFF; 8], false))]);
+        assert!(matches!(
+            decoder.unpack(&[0xFF; 8], 0),
+            Err(HuffmanError::InvalidDictionaryIndex)
+        ));
+    }
+
+    #[test]
+    fn unpack_max_depth() {
+        // Each entry references the next one once and a terminal entry 0 for the rest
+        let chain = |len: u8| {
+            let mut dictionary: HuffmanDictionary = vec![Some((b"a".to_vec(), true))];
+            for i in 1..len {
+                let next = if i + 1 < len { i + 1 } else { 0 };
+                let mut data = vec![0xFF; 8];
+                data[0] = 0xFF - next;
+                dictionary.push(Some((data, false)));
+            }
+            byte_decoder(dictionary)
+        };
+        let data = [0xFE, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF];
+
+        assert!(chain(10).unpack(&data, 0).is_ok());
+        assert!(matches!(
+            chain(100).unpack(&data, 0),
+            Err(HuffmanError::InvalidDictionaryIndex)
+        ));
+    }
+
+    #[test]
+    fn load_no_cdic_records() {
+        let mut decoder = HuffmanDecoder::default();
+        assert!(matches!(
+            decoder.load_cdic_records(&[]),
+            Err(HuffmanError::MissingHuffRecord)
+        ));
+    }
+}
@@ -1,3 +1,9 @@
+/// Largest distance of a back-reference, it's stored in 11 bits
+const MAX_DISTANCE: usize = 2047;
+/// Shortest and longest length of a back-reference
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 10;
+
 pub fn decompress(data: &[u8]) -> Vec<u8> {
     let length = data.len();
     let mut pos: usize = 0;
@@ -57,7 +63,7 @@ pub fn decompress(data: &[u8]) -> Vec<u8> {
             }
             // Data is LZ77-compressed
             0x80..=0xbf => {
-                if pos >= text.len() {
+                if pos >= length {
                     return text;
                 }
 
@@ -75,3 +81,89 @@ pub fn decompress(data: &[u8]) -> Vec<u8> {
 
     text
 }
+
+/// Compresses data with the PalmDOC LZ77 variant. Back-references never overlap the
+/// bytes they produce, as `decompress` copies them from already decompressed text.
+pub fn compress(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len());
+    let mut pos = 0;
+
+    while pos < data.len() {
+        if let Some((distance, len)) = find_match(data, pos) {
+            let pair = 0x8000 | ((distance as u16) << 3) | (len - MIN_MATCH) as u16;
+            out.extend_from_slice(&pair.to_be_bytes());
+            pos += len;
+            continue;
+        }
+
+        let byte = data[pos];
+        match data.get(pos + 1) {
+            // Space followed by a character from 0x40..=0x7f is stored as a single byte
+            Some(&next) if byte == b' ' && (0x40..=0x7f).contains(&next) => {
+                out.push(next ^ 0x80);
+                pos += 2;
+            }
+            _ if is_literal(byte) => {
+                out.push(byte);
+                pos += 1;
+            }
+            // Up to 8 other bytes are stored prefixed by their count
+            _ => {
+                let len = data[pos..]
+                    .iter()
+                    .take(8)
+                    .take_while(|&&b| !is_literal(b))
+                    .count();
+                out.push(len as u8);
+                out.extend_from_slice(&data[pos..pos + len]);
+                pos += len;
+            }
+        }
+    }
+
+    out
+}
+
+fn is_literal(byte: u8) -> bool {
+    matches!(byte, 0x0 | 0x09..=0x7f)
+}
+
+/// Finds the longest earlier occurrence of bytes at `pos`, returning its distance and length
+fn find_match(data: &[u8], pos: usize) -> Option<(usize, usize)> {
+    let window_start = pos.saturating_sub(MAX_DISTANCE);
+    let window = &data[window_start..pos];
+
+    (MIN_MATCH..=MAX_MATCH.min(data.len() - pos))
+        .rev()
+        .find_map(|len| {
+            let chunk = &data[pos..pos + len];
+            window
+                .windows(len)
+                .rposition(|w| w == chunk)
+                .map(|start| (pos - window_start - start, len))
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn compress_round_trip() {
+        let text = "<p>The quick brown fox jumps over the lazy dog. The quick brown fox.</p>\
+            \0\x01 zażółć gęślą jaźń"
+            .repeat(100);
+        let compressed = compress(text.as_bytes());
+        assert!(compressed.len() < text.len());
+        assert_eq!(decompress(&compressed), text.as_bytes());
+    }
+
+    #[test]
+    fn compress_encodings() {
+        assert_eq!(compress(b"ab"), b"ab");
+        assert_eq!(compress(b" a"), [b'a' ^ 0x80]);
+        assert_eq!(compress(b"\xE9\xE9"), b"\x02\xE9\xE9");
+        assert_eq!(compress(b"abcabc"), [b'a', b'b', b'c', 0x80, 0x18]);
+        assert!(compress(b"").is_empty());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{Reader, Writer};
 
 use indexmap::IndexMap;
-use std::io;
+use std::{collections::HashSet, io};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -200,7 +200,45 @@ impl From<u32> for ExthRecord {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Content type of the document stored in `Cdetype` EXTH record.
+pub enum CdeType {
+    /// PDOC
+    PersonalDoc,
+    /// EBOK
+    Ebook,
+    /// EBSP
+    EbookSample,
+    Other(String),
+}
+
+impl From<&[u8]> for CdeType {
+    fn from(code: &[u8]) -> Self {
+        match code {
+            b"PDOC" => CdeType::PersonalDoc,
+            b"EBOK" => CdeType::Ebook,
+            b"EBSP" => CdeType::EbookSample,
+            other => CdeType::Other(String::from_utf8_lossy(other).to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Identifies the software that created the book. Read from `CreatorSoftware` and
+/// following version EXTH records.
+///
+/// Known ids: 1=mobigen, 2=Mobipocket Creator, 200=kindlegen (Windows), 201=kindlegen (Linux),
+/// 202=kindlegen (Mac). Warning: Calibre creates fake creator entries, pretending to be a Linux
+/// kindlegen 1.2 (201, 1, 2, 33307) for normal ebooks and a non-public Linux kindlegen 2.0
+/// (201, 2, 0, 101) for periodicals.
+pub struct CreatorSoftware {
+    pub id: u32,
+    pub major_version: Option<u32>,
+    pub minor_version: Option<u32>,
+    pub build_number: Option<u32>,
+}
+
+#[derive(Debug, Default)]
 /// Optional header containing extended information. If the MOBI header
 /// indicates that there's an EXTH header, it follows immediately after
 /// the MOBI header.
@@ -209,76 +247,132 @@ pub struct ExtHeader {
     pub header_length: u32,
     pub record_count: u32,
     pub records: IndexMap<ExthRecord, Vec<Vec<u8>>>,
+    /// Position and index into `records` of every record in the order they were read,
+    /// used by `iter_in_order`. Left empty for headers that weren't parsed.
+    pub order: Vec<(u32, usize)>,
 }
 
+impl PartialEq for ExtHeader {
+    /// Records are compared regardless of the order in which they were read
+    fn eq(&self, other: &Self) -> bool {
+        self.identifier == other.identifier
+            && self.header_length == other.header_length
+            && self.record_count == other.record_count
+            && self.records == other.records
+    }
+}
+
+impl Eq for ExtHeader {}
+
 impl ExtHeader {
     /// Parse a EXTH header from the content. Reader must be at starting
     /// location of exth header.
     pub(crate) fn parse<R: io::Read>(
         reader: &mut Reader<R>,
+    ) -> Result<ExtHeader, ExthRecordParseError> {
+        Self::parse_with(reader, false)
+    }
+
+    /// Parse a EXTH header like `parse` but skip records that declare a length less
+    /// than 8 bytes instead of failing.
+    pub(crate) fn parse_lenient<R: io::Read>(
+        reader: &mut Reader<R>,
+    ) -> Result<ExtHeader, ExthRecordParseError> {
+        Self::parse_with(reader, true)
+    }
+
+    fn parse_with<R: io::Read>(
+        reader: &mut Reader<R>,
+        lenient: bool,
     ) -> Result<ExtHeader, ExthRecordParseError> {
         let mut extheader = ExtHeader {
             identifier: reader.read_u32_be()?,
             header_length: reader.read_u32_be()?,
             record_count: reader.read_u32_be()?,
             records: IndexMap::new(),
+            order: vec![],
         };
 
         if &extheader.identifier.to_be_bytes() == b"EXTH" {
-            extheader.populate_records(reader)?;
+            extheader.populate_records(reader, lenient)?;
             Ok(extheader)
         } else {
             Err(ExthRecordParseError::InvalidIdentifier)
         }
     }
 
-    /// Gets header records
+    /// Gets header records. If there are less records than declared the records
+    /// read until the end of content are kept.
     fn populate_records<R: io::Read>(
         &mut self,
         reader: &mut Reader<R>,
+        lenient: bool,
     ) -> Result<(), ExthRecordParseError> {
         for _i in 0..self.record_count {
-            let record_type = ExthRecord::from(reader.read_u32_be()?);
-            let record_len = reader.read_u32_be()?;
-
-            let num_bytes = match record_len.checked_sub(8) {
-                None => return Err(ExthRecordParseError::RecordTooSmall),
-                Some(num_bytes) => num_bytes,
+            let (record_type, record_data) = match Self::read_record(reader) {
+                Ok(record) => record,
+                // The record only consists of the type and length so the next record
+                // should begin right after it.
+                Err(ExthRecordParseError::RecordTooSmall) if lenient => continue,
+                Err(ExthRecordParseError::IoError(e))
+                    if e.kind() == io::ErrorKind::UnexpectedEof =>
+                {
+                    break
+                }
+                Err(e) => return Err(e),
             };
 
-            let record_data = reader.read_vec_header(num_bytes as usize)?;
-
-            if let Some(record) = self.records.get_mut(&record_type) {
-                record.push(record_data);
-            } else {
-                self.records.insert(record_type, vec![record_data]);
-            }
+            let record = self.records.entry(record_type).or_default();
+            self.order.push((record_type.position(), record.len()));
+            record.push(record_data);
         }
 
         Ok(())
     }
 
+    fn read_record<R: io::Read>(
+        reader: &mut Reader<R>,
+    ) -> Result<(ExthRecord, Vec<u8>), ExthRecordParseError> {
+        let record_type = ExthRecord::from(reader.read_u32_be()?);
+        let record_len = reader.read_u32_be()?;
+
+        let num_bytes = match record_len.checked_sub(8) {
+            None => return Err(ExthRecordParseError::RecordTooSmall),
+            Some(num_bytes) => num_bytes,
+        };
+
+        Ok((record_type, reader.read_vec_header(num_bytes as usize)?))
+    }
+
     pub(crate) fn write<W: io::Write>(&self, w: &mut Writer<W>) -> io::Result<()> {
         w.write_be(self.identifier)?;
         w.write_be(
             12u32
                 + self
-                    .records
-                    .iter()
-                    .map(|(_, d)| 8 + d.len() as u32)
+                    .iter_in_order()
+                    .map(|(_, data)| 8 + data.len() as u32)
                     .sum::<u32>(),
         )?;
-        w.write_be(self.records.len() as u32)?;
-        for (&id, records) in self.records.iter() {
-            for record_data in records {
-                w.write_be(id.position())?;
-                w.write_be(record_data.len() as u32 + 8)?;
-                w.write_be(record_data)?;
-            }
+        w.write_be(self.actual_record_count() as u32)?;
+        for (position, record_data) in self.iter_in_order() {
+            w.write_be(position)?;
+            w.write_be(record_data.len() as u32 + 8)?;
+            w.write_be(record_data)?;
         }
         Ok(())
     }
 
+    /// Returns the number of records declared in this header
+    pub fn declared_record_count(&self) -> u32 {
+        self.record_count
+    }
+
+    /// Returns the number of records actually read, counting every occurrence of
+    /// duplicate records
+    pub fn actual_record_count(&self) -> usize {
+        self.records.values().map(Vec::len).sum()
+    }
+
     /// Returns exth record data located at position. This is a low level function intended
     /// to use with wrapper get_record, but exposed for convienience.
     pub fn get_record_position(&self, position: u32) -> Option<&Vec<Vec<u8>>> {
@@ -296,6 +390,38 @@ impl ExtHeader {
         self.records.iter()
     }
 
+    /// Returns an iterator yielding position and data of every record occurrence in the order
+    /// they were read. Records added to `records` after parsing follow in map order.
+    pub fn iter_in_order(&self) -> impl Iterator<Item = (u32, &[u8])> {
+        let read = self.order.iter().filter_map(move |&(position, i)| {
+            let data = self.records.get(&ExthRecord::from(position))?.get(i)?;
+            Some((position, data.as_slice()))
+        });
+        let read_set: HashSet<_> = self.order.iter().copied().collect();
+        let added = self
+            .records
+            .iter()
+            .flat_map(|(record, data)| {
+                let position = record.position();
+                data.iter().enumerate().map(move |(i, d)| (position, i, d))
+            })
+            .filter(move |(position, i, _)| !read_set.contains(&(*position, *i)))
+            .map(|(position, _, d)| (position, d.as_slice()));
+        read.chain(added)
+    }
+
+    /// Returns an iterator yielding position and data of records whose position matches
+    /// the predicate, for example all records in a range of positions.
+    pub fn records_matching(
+        &self,
+        f: impl Fn(u32) -> bool,
+    ) -> impl Iterator<Item = (u32, &Vec<Vec<u8>>)> {
+        self.records
+            .iter()
+            .map(|(record, data)| (record.position(), data))
+            .filter(move |(position, _)| f(*position))
+    }
+
     /// Returns an iterator over all available EXTH records and performs a loseless conversion of
     /// record data to string.
     pub fn records(&self) -> impl Iterator<Item = (&ExthRecord, Vec<String>)> {
@@ -309,11 +435,30 @@ impl ExtHeader {
         })
     }
 
+    /// Returns first record data interpreted as a big endian u32. Returns `None` if the
+    /// record is missing or its data is shorter than 4 bytes.
+    pub(crate) fn get_record_u32(&self, record: ExthRecord) -> Option<u32> {
+        self.get_record(record)
+            .and_then(|r| r.first())
+            .and_then(|r| r.get(..4))
+            .map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
     pub(crate) fn get_record_string_lossy(&self, record: ExthRecord) -> Option<String> {
         self.get_record(record)
             .and_then(|r| r.first())
             .map(|r| String::from_utf8_lossy(r).to_string())
     }
+
+    pub(crate) fn get_record_strings_lossy(&self, record: ExthRecord) -> Vec<String> {
+        self.get_record(record)
+            .map(|r| {
+                r.iter()
+                    .map(|d| String::from_utf8_lossy(d).to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
 }
 
 #[cfg(test)]
@@ -347,6 +492,7 @@ mod tests {
             header_length: 1109,
             record_count: 11,
             records,
+            ..Default::default()
         };
 
         let mut reader = book::u8_reader(book::BOOK.to_vec());
@@ -386,6 +532,135 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_iter_in_order() {
+        let mut reader = book::u8_reader(book::BOOK.to_vec());
+        let parsed_header = ExtHeader::parse(&mut reader).unwrap();
+        let records: Vec<_> = parsed_header.iter_in_order().collect();
+
+        assert_eq!(records.len(), parsed_header.record_count as usize);
+        assert_eq!(
+            records.iter().map(|(p, _)| *p).collect::<Vec<_>>(),
+            vec![101, 103, 100, 503, 106, 108, 104, 106, 201, 203, 202]
+        );
+        assert_eq!(records[4], (106, &b"2010-12-21T00:00:00+00:00"[..]));
+        assert_eq!(records[7], (106, &b"2010-12-21T00:00:00+00:00"[..]));
+    }
+
+    #[test]
+    fn test_iter_in_order_interleaved_duplicates() {
+        let mut bytes = b"EXTH".to_vec();
+        bytes.extend_from_slice(&[0, 0, 0, 48, 0, 0, 0, 4]);
+        for (position, data) in [(100, b"A"), (101, b"P"), (100, b"B"), (503, b"T")] {
+            bytes.extend_from_slice(&(position as u32).to_be_bytes());
+            bytes.extend_from_slice(&9u32.to_be_bytes());
+            bytes.extend_from_slice(data);
+        }
+
+        let mut exth = ExtHeader::parse(&mut book::u8_reader(bytes.clone())).unwrap();
+        assert_eq!(
+            exth.iter_in_order().collect::<Vec<_>>(),
+            vec![(100, &b"A"[..]), (101, b"P"), (100, b"B"), (503, b"T")]
+        );
+
+        let mut written = vec![];
+        exth.write(&mut Writer::new(&mut written)).unwrap();
+        assert_eq!(written, bytes);
+
+        exth.records
+            .get_mut(&ExthRecord::Author)
+            .unwrap()
+            .push(b"C".to_vec());
+        exth.records.shift_remove(&ExthRecord::Publisher);
+        assert_eq!(
+            exth.iter_in_order().collect::<Vec<_>>(),
+            vec![(100, &b"A"[..]), (100, b"B"), (503, b"T"), (100, b"C")]
+        );
+    }
+
+    #[test]
+    fn test_over_declared_records() {
+        let mut bytes = b"EXTH".to_vec();
+        bytes.extend_from_slice(&24u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&100u32.to_be_bytes());
+        bytes.extend_from_slice(&12u32.to_be_bytes());
+        bytes.extend_from_slice(b"Anon");
+        // Truncated second record
+        bytes.extend_from_slice(&101u32.to_be_bytes());
+
+        let parsed_header = ExtHeader::parse(&mut book::u8_reader(bytes)).unwrap();
+        assert_eq!(parsed_header.declared_record_count(), 3);
+        assert_eq!(parsed_header.actual_record_count(), 1);
+        assert_eq!(
+            parsed_header.get_record(ExthRecord::Author),
+            Some(&vec![b"Anon".to_vec()])
+        );
+
+        let parsed_header = ExtHeader::parse(&mut book::u8_reader(book::BOOK.to_vec())).unwrap();
+        assert_eq!(parsed_header.declared_record_count(), 11);
+        assert_eq!(parsed_header.actual_record_count(), 11);
+    }
+
+    #[test]
+    fn test_lenient_records() {
+        let mut bytes = b"EXTH".to_vec();
+        bytes.extend_from_slice(&36u32.to_be_bytes());
+        bytes.extend_from_slice(&3u32.to_be_bytes());
+        bytes.extend_from_slice(&100u32.to_be_bytes());
+        bytes.extend_from_slice(&12u32.to_be_bytes());
+        bytes.extend_from_slice(b"Anon");
+        // Malformed record declaring length shorter than its own header
+        bytes.extend_from_slice(&101u32.to_be_bytes());
+        bytes.extend_from_slice(&4u32.to_be_bytes());
+        bytes.extend_from_slice(&503u32.to_be_bytes());
+        bytes.extend_from_slice(&13u32.to_be_bytes());
+        bytes.extend_from_slice(b"Title");
+
+        assert!(matches!(
+            ExtHeader::parse(&mut book::u8_reader(bytes.clone())),
+            Err(ExthRecordParseError::RecordTooSmall)
+        ));
+
+        let parsed_header = ExtHeader::parse_lenient(&mut book::u8_reader(bytes)).unwrap();
+        assert_eq!(parsed_header.actual_record_count(), 2);
+        assert_eq!(
+            parsed_header.get_record(ExthRecord::Author),
+            Some(&vec![b"Anon".to_vec()])
+        );
+        assert_eq!(
+            parsed_header.get_record(ExthRecord::Title),
+            Some(&vec![b"Title".to_vec()])
+        );
+        assert_eq!(parsed_header.get_record(ExthRecord::Publisher), None);
+    }
+
+    #[test]
+    fn records_matching() {
+        let exth = ExtHeader::parse(&mut book::u8_reader(book::BOOK.to_vec())).unwrap();
+        let positions: Vec<u32> = exth
+            .records_matching(|position| (200..=209).contains(&position))
+            .map(|(position, _)| position)
+            .collect();
+        assert_eq!(positions, vec![201, 203, 202]);
+        assert_eq!(
+            exth.records_matching(|position| position == 202).next(),
+            Some((202, &vec![b"\0\0\0\x01".to_vec()]))
+        );
+        assert_eq!(exth.records_matching(|_| false).count(), 0);
+    }
+
+    #[test]
+    fn cde_type() {
+        assert_eq!(CdeType::from(&b"PDOC"[..]), CdeType::PersonalDoc);
+        assert_eq!(CdeType::from(&b"EBOK"[..]), CdeType::Ebook);
+        assert_eq!(CdeType::from(&b"EBSP"[..]), CdeType::EbookSample);
+        assert_eq!(
+            CdeType::from(&b"MAGZ"[..]),
+            CdeType::Other(String::from("MAGZ"))
+        );
+    }
+
     mod records {
         use crate::book;
         use crate::headers::{ExtHeader, ExthRecord};
@@ -1,7 +1,7 @@
 use crate::{Reader, Writer};
 
 #[cfg(feature = "time")]
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDateTime};
 use std::io;
 use thiserror::Error;
 
@@ -11,14 +11,71 @@ pub enum HeaderParseError {
     IsTopazError,
     #[error("this book is an Amazon KFX book and it cannot be processed")]
     IsKfxError,
-    #[error("expected type header identifier BOOK or TEXT")]
+    #[error("this book is a PalmDOC book with an invalid header")]
+    NotMobiButPalmDoc,
+    #[error("expected type header identifier BOOK or TEXt")]
     InvalidTypeIdentifier,
-    #[error("expected creator header identifier MOBI or READ")]
+    #[error("expected creator header identifier MOBI or REAd")]
     InvalidCreatorIdentifier,
     #[error(transparent)]
     IoError(#[from] io::Error),
 }
 
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+/// Raw creation and last modification timestamps in seconds since unix epoch
+pub struct Timestamps {
+    pub created: u32,
+    pub modified: u32,
+}
+
+impl Timestamps {
+    #[cfg(feature = "time")]
+    /// Returns a chrono::NaiveDateTime timestamp of file creation
+    /// This field is only available using `time` feature
+    pub fn created_datetime(&self) -> NaiveDateTime {
+        to_datetime(self.created)
+    }
+
+    #[cfg(feature = "time")]
+    /// Returns a chrono::NaiveDateTime timestamp of file modification
+    /// This field is only available using `time` feature
+    pub fn mod_datetime(&self) -> NaiveDateTime {
+        to_datetime(self.modified)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Default, Clone, Copy)]
+/// Flags of the PDB header attributes field
+pub struct HeaderAttributes(pub u16);
+
+impl HeaderAttributes {
+    const READ_ONLY: u16 = 0x0002;
+    const BACKUP: u16 = 0x0008;
+    const RESET_AFTER_INSTALL: u16 = 0x0020;
+
+    /// Returns true if the database is read only
+    pub fn is_read_only(&self) -> bool {
+        self.0 & Self::READ_ONLY != 0
+    }
+
+    /// Returns true if the database should be backed up
+    pub fn is_backup(&self) -> bool {
+        self.0 & Self::BACKUP != 0
+    }
+
+    /// Returns true if the device should be reset after installing the database
+    pub fn is_reset_after_install(&self) -> bool {
+        self.0 & Self::RESET_AFTER_INSTALL != 0
+    }
+}
+
+#[cfg(feature = "time")]
+fn to_datetime(timestamp: u32) -> NaiveDateTime {
+    DateTime::from_timestamp(i64::from(timestamp), 0)
+        .map(|dt| dt.naive_utc())
+        .unwrap_or_default()
+}
+
 #[derive(Debug, PartialEq, Eq, Default)]
 /// Structure that holds header information
 pub struct Header {
@@ -62,14 +119,14 @@ impl Header {
             sort_info_id: reader.read_u32_be()?,
             type_: {
                 let ty = reader.read_u32_be()?.to_be_bytes();
-                if &ty != b"BOOK" && &ty != b"TEXT" {
+                if !matches!(&ty, b"BOOK" | b"TEXt" | b"TEXT") {
                     return Err(HeaderParseError::InvalidTypeIdentifier);
                 }
                 ty
             },
             creator: {
                 let creator = reader.read_u32_be()?.to_be_bytes();
-                if &creator != b"MOBI" && &creator != b"READ" {
+                if !matches!(&creator, b"MOBI" | b"REAd" | b"READ") {
                     return Err(HeaderParseError::InvalidCreatorIdentifier);
                 }
                 creator
@@ -102,18 +159,38 @@ impl Header {
         w.write_be(num_records)
     }
 
+    /// Returns decoded flags of the attributes field
+    pub fn attributes_decoded(&self) -> HeaderAttributes {
+        HeaderAttributes(self.attributes)
+    }
+
+    /// Returns true if type and creator identifiers are those of a PalmDOC book, such
+    /// books usually don't have a MOBI header. PalmDOC books use `TEXt` and `REAd`,
+    /// upper case variants are accepted too.
+    pub(crate) fn is_palmdoc(&self) -> bool {
+        matches!(&self.type_, b"TEXt" | b"TEXT") && matches!(&self.creator, b"REAd" | b"READ")
+    }
+
+    /// Returns raw creation and last modification timestamps
+    pub(crate) fn timestamps(&self) -> Timestamps {
+        Timestamps {
+            created: self.created,
+            modified: self.modified,
+        }
+    }
+
     #[cfg(feature = "time")]
     /// Returns a chrono::NaiveDateTime timestamp of file creation
     /// This field is only available using `time` feature
     pub(crate) fn created_datetime(&self) -> NaiveDateTime {
-        NaiveDateTime::from_timestamp_opt(i64::from(self.created), 0).unwrap_or_default()
+        to_datetime(self.created)
     }
 
     #[cfg(feature = "time")]
     /// Returns a chrono::NaiveDateTime timestamp of file modification
     /// This field is only available using `time` feature
     pub(crate) fn mod_datetime(&self) -> NaiveDateTime {
-        NaiveDateTime::from_timestamp_opt(i64::from(self.modified), 0).unwrap_or_default()
+        to_datetime(self.modified)
     }
 
     #[cfg(not(feature = "time"))]
@@ -161,6 +238,50 @@ mod tests {
         assert_eq!(header, parsed_header.unwrap());
     }
 
+    #[test]
+    fn timestamps() {
+        let header = Header::parse(&mut book::u8_reader(book::HEADER.to_vec())).unwrap();
+        let timestamps = header.timestamps();
+        assert_eq!(
+            timestamps,
+            Timestamps {
+                created: 1299709979,
+                modified: 1299709979
+            }
+        );
+
+        #[cfg(feature = "time")]
+        {
+            let expected = chrono::NaiveDate::from_ymd_opt(2011, 3, 9)
+                .unwrap()
+                .and_hms_opt(22, 32, 59)
+                .unwrap();
+            assert_eq!(timestamps.created_datetime(), expected);
+            assert_eq!(timestamps.mod_datetime(), expected);
+        }
+    }
+
+    #[test]
+    fn attributes() {
+        let mut header = Header::parse(&mut book::u8_reader(book::HEADER.to_vec())).unwrap();
+        let attributes = header.attributes_decoded();
+        assert!(!attributes.is_read_only());
+        assert!(!attributes.is_backup());
+        assert!(!attributes.is_reset_after_install());
+
+        header.attributes = 0x000A;
+        let attributes = header.attributes_decoded();
+        assert!(attributes.is_read_only());
+        assert!(attributes.is_backup());
+        assert!(!attributes.is_reset_after_install());
+
+        header.attributes = 0x0020;
+        let attributes = header.attributes_decoded();
+        assert!(!attributes.is_read_only());
+        assert!(!attributes.is_backup());
+        assert!(attributes.is_reset_after_install());
+    }
+
     #[test]
     fn write() {
         let header = book::HEADER.to_vec();
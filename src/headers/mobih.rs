@@ -1,8 +1,10 @@
 use crate::writer::WriteBeBytes;
 use crate::{Reader, Writer};
 
-use std::io;
+use std::convert::Infallible;
 use std::io::Cursor;
+use std::str::FromStr;
+use std::{fmt, io};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,7 +20,7 @@ pub enum MobiHeaderParseError {
 const DRM_ON_FLAG: u32 = 0xFFFF_FFFF;
 const EXTH_ON_FLAG: u32 = 0x40;
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 pub enum MobiType {
     MobiPocketBook,
     PalmDocBook,
@@ -32,12 +34,28 @@ pub enum MobiType {
     PPT,
     TEXT,
     HTML,
+    #[default]
     Unknown,
 }
 
-impl Default for MobiType {
-    fn default() -> Self {
-        MobiType::Unknown
+impl fmt::Display for MobiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use MobiType::*;
+        f.write_str(match self {
+            MobiPocketBook => "MobiPocket Book",
+            PalmDocBook => "PalmDOC Book",
+            Audio => "Audio",
+            News => "News",
+            NewsFeed => "News Feed",
+            NewsMagazine => "News Magazine",
+            PICS => "PICS",
+            WORD => "WORD",
+            XLS => "XLS",
+            PPT => "PPT",
+            TEXT => "TEXT",
+            HTML => "HTML",
+            Unknown => "Unknown",
+        })
     }
 }
 
@@ -62,6 +80,16 @@ impl From<u32> for MobiType {
     }
 }
 
+impl MobiType {
+    /// Checks if this is a periodical type, these contain sections and articles
+    pub fn is_periodical(&self) -> bool {
+        matches!(
+            self,
+            MobiType::News | MobiType::NewsFeed | MobiType::NewsMagazine
+        )
+    }
+}
+
 impl From<MobiType> for u32 {
     fn from(ty: MobiType) -> u32 {
         use MobiType::*;
@@ -89,19 +117,14 @@ impl WriteBeBytes for MobiType {
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 pub enum TextEncoding {
     CP1252,
+    #[default]
     UTF8,
     Unknown(u32),
 }
 
-impl Default for TextEncoding {
-    fn default() -> Self {
-        TextEncoding::UTF8
-    }
-}
-
 impl From<u32> for TextEncoding {
     fn from(num: u32) -> Self {
         match num {
@@ -122,13 +145,56 @@ impl From<TextEncoding> for u32 {
     }
 }
 
+impl TextEncoding {
+    /// Returns a human readable name of this encoding
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextEncoding::CP1252 => "CP1252",
+            TextEncoding::UTF8 => "UTF-8",
+            TextEncoding::Unknown(_) => "Unknown",
+        }
+    }
+
+    /// Returns the codepage number of this encoding as stored in Mobi header
+    pub fn codepage(&self) -> u32 {
+        u32::from(*self)
+    }
+}
+
 impl WriteBeBytes for TextEncoding {
     fn write_be_bytes<W: io::Write>(&self, writer: &mut W) -> io::Result<usize> {
         u32::from(*self).write_be_bytes(writer)
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// DRM descriptor of a book as found in the Mobi header
+pub struct DrmInfo {
+    pub offset: u32,
+    pub count: u32,
+    pub size: u32,
+    pub flags: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Version of the MOBI format used by a book
+pub enum MobiFormat {
+    Mobi6,
+    Kf8,
+    Other(u32),
+}
+
+impl From<u32> for MobiFormat {
+    fn from(n: u32) -> MobiFormat {
+        match n {
+            6 => MobiFormat::Mobi6,
+            8 => MobiFormat::Kf8,
+            n => MobiFormat::Other(n),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
 /// Strcture that holds Mobi header information
 pub struct MobiHeader {
     pub identifier: u32,
@@ -173,10 +239,10 @@ pub struct MobiHeader {
     unused_5: u32, // flis record count?
     unused_6: u64,
     unused_7: u32,
-    first_compilation_data_section_count: u32,
-    data_section_count: u32,
+    pub first_compilation_data_section_count: u32,
+    pub data_section_count: u32,
     unused_8: u32,
-    extra_record_data_flags: u32,
+    pub extra_record_data_flags: u32,
     pub first_index_record: u32,
     unused_9: Vec<u8>,
 }
@@ -237,6 +303,20 @@ impl Default for MobiHeader {
 }
 
 impl MobiHeader {
+    /// Returns a header describing a PalmDOC book which has no MOBI header. Text of such
+    /// books is stored in `record_count` records following the first one and is encoded
+    /// in CP1252.
+    pub(crate) fn palmdoc(record_count: u16) -> MobiHeader {
+        MobiHeader {
+            text_encoding: TextEncoding::CP1252,
+            first_content_record: 1,
+            last_content_record: record_count,
+            first_non_book_index: u32::from(record_count) + 1,
+            first_image_index: u32::from(record_count) + 1,
+            ..Default::default()
+        }
+    }
+
     /// Parse a Mobi header from the content. The reader must be advanced to the starting
     /// position of the Mobi header.
     pub(crate) fn parse<R: io::Read>(
@@ -386,11 +466,28 @@ impl MobiHeader {
         (self.exth_flags & EXTH_ON_FLAG) != 0
     }
 
+    pub(crate) fn enable_exth_header(&mut self) {
+        self.exth_flags |= EXTH_ON_FLAG;
+    }
+
     /// Checks if there is DRM on this book
     pub fn has_drm(&self) -> bool {
         self.drm_offset != DRM_ON_FLAG
     }
 
+    /// Returns DRM descriptor if there is DRM on this book
+    pub fn drm_info(&self) -> Option<DrmInfo> {
+        if !self.has_drm() {
+            return None;
+        }
+        Some(DrmInfo {
+            offset: self.drm_offset,
+            count: self.drm_count,
+            size: self.drm_size,
+            flags: self.drm_flags,
+        })
+    }
+
     /// Converts numerical value into a type
     pub fn mobi_type(&self) -> MobiType {
         self.mobi_type
@@ -405,10 +502,64 @@ impl MobiHeader {
     pub fn language(&self) -> Language {
         self.language_code
     }
+
+    /// Returns the version of the MOBI format
+    pub fn format(&self) -> MobiFormat {
+        MobiFormat::from(self.format_version)
+    }
+
+    /// Returns the language of lookup words in dictionaries
+    pub fn input_language_parsed(&self) -> Language {
+        Language::from(self.input_language as u8)
+    }
+
+    /// Returns the language of definitions in dictionaries
+    pub fn output_language_parsed(&self) -> Language {
+        Language::from(self.output_language as u8)
+    }
+
+    /// Flags describing trailing entries appended to each text record. Bit 0 indicates
+    /// multibyte character overlap, every other set bit adds a variable length trailing entry.
+    pub fn extra_record_data_flags(&self) -> u32 {
+        self.extra_record_data_flags
+    }
+
+    /// Moves record indices pointing at or after `from` by `delta` records, used when
+    /// records are inserted or removed. Unset indices are left as they are.
+    pub(crate) fn shift_record_indices(&mut self, from: u32, delta: i64) {
+        let shift = |index: &mut u32| {
+            if *index != u32::MAX && *index >= from {
+                *index = (i64::from(*index) + delta) as u32;
+            }
+        };
+        for index in IntoIterator::into_iter([
+            &mut self.ortho_index,
+            &mut self.inflect_index,
+            &mut self.index_names,
+            &mut self.index_keys,
+            &mut self.first_non_book_index,
+            &mut self.first_image_index,
+            &mut self.first_huff_record,
+            &mut self.fcis_record,
+            &mut self.flis_record,
+            &mut self.first_index_record,
+        ])
+        .chain(self.extra_indices.iter_mut())
+        {
+            shift(index);
+        }
+
+        let mut last_content_record = u32::from(self.last_content_record);
+        if self.last_content_record != u16::MAX {
+            shift(&mut last_content_record);
+            self.last_content_record = last_content_record as u16;
+        }
+    }
 }
 
-#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+#[derive(Debug, PartialEq, Eq, Copy, Clone, Default)]
 pub enum Language {
+    #[default]
     Neutral,
     Afrikaans,
     Albanian,
@@ -488,6 +639,277 @@ pub enum Language {
     Unknown,
 }
 
+impl Language {
+    /// Returns the BCP 47 language tag of this language. Both `Neutral` and `Unknown`
+    /// map to `und`, the tag of an undetermined language.
+    pub fn to_bcp47(&self) -> &'static str {
+        use Language::*;
+        match self {
+            Neutral => "und",
+            Afrikaans => "af",
+            Albanian => "sq",
+            Arabic => "ar",
+            Armenian => "hy",
+            Assamese => "as",
+            Azeri => "az",
+            Basque => "eu",
+            Belarusian => "be",
+            Bengali => "bn",
+            Bulgarian => "bg",
+            Catalan => "ca",
+            Chinese => "zh",
+            Czech => "cs",
+            Danish => "da",
+            Dutch => "nl",
+            English => "en",
+            Estonian => "et",
+            Faeroese => "fo",
+            Farsi => "fa",
+            Finnish => "fi",
+            French => "fr",
+            Georgian => "ka",
+            German => "de",
+            Greek => "el",
+            Gujarati => "gu",
+            Hebrew => "he",
+            Hindi => "hi",
+            Hungarian => "hu",
+            Icelandic => "is",
+            Indonesian => "id",
+            Italian => "it",
+            Japanese => "ja",
+            Kannada => "kn",
+            Kazak => "kk",
+            Konkani => "kok",
+            Korean => "ko",
+            Latvian => "lv",
+            Lithuanian => "lt",
+            Macedonian => "mk",
+            Malay => "ms",
+            Malayalam => "ml",
+            Maltese => "mt",
+            Marathi => "mr",
+            Nepali => "ne",
+            Norwegian => "no",
+            Oriya => "or",
+            Polish => "pl",
+            Portuguese => "pt",
+            Punjabi => "pa",
+            Rhaetoromanic => "rm",
+            Romanian => "ro",
+            Russian => "ru",
+            Sami => "se",
+            Sanskrit => "sa",
+            Serbian => "sr",
+            Slovak => "sk",
+            Slovenian => "sl",
+            Sorbian => "hsb",
+            Spanish => "es",
+            Sutu => "st",
+            Swahili => "sw",
+            Swedish => "sv",
+            Tamil => "ta",
+            Tatar => "tt",
+            Telugu => "te",
+            Thai => "th",
+            Tsonga => "ts",
+            Tswana => "tn",
+            Turkish => "tr",
+            Ukrainian => "uk",
+            Urdu => "ur",
+            Uzbek => "uz",
+            Vietnamese => "vi",
+            Xhosa => "xh",
+            Zulu => "zu",
+            Unknown => "und",
+        }
+    }
+
+    /// Parses a BCP 47 language tag. Only the primary language subtag is considered,
+    /// so `en-US` is `English`. Returns `Unknown` for unsupported languages.
+    pub fn from_bcp47(tag: &str) -> Language {
+        use Language::*;
+        let primary = tag.split(['-', '_']).next().unwrap_or_default();
+        match primary.to_ascii_lowercase().as_str() {
+            "und" => Neutral,
+            "af" => Afrikaans,
+            "sq" => Albanian,
+            "ar" => Arabic,
+            "hy" => Armenian,
+            "as" => Assamese,
+            "az" => Azeri,
+            "eu" => Basque,
+            "be" => Belarusian,
+            "bn" => Bengali,
+            "bg" => Bulgarian,
+            "ca" => Catalan,
+            "zh" => Chinese,
+            "cs" => Czech,
+            "da" => Danish,
+            "nl" => Dutch,
+            "en" => English,
+            "et" => Estonian,
+            "fo" => Faeroese,
+            "fa" => Farsi,
+            "fi" => Finnish,
+            "fr" => French,
+            "ka" => Georgian,
+            "de" => German,
+            "el" => Greek,
+            "gu" => Gujarati,
+            "he" => Hebrew,
+            "hi" => Hindi,
+            "hu" => Hungarian,
+            "is" => Icelandic,
+            "id" => Indonesian,
+            "it" => Italian,
+            "ja" => Japanese,
+            "kn" => Kannada,
+            "kk" => Kazak,
+            "kok" => Konkani,
+            "ko" => Korean,
+            "lv" => Latvian,
+            "lt" => Lithuanian,
+            "mk" => Macedonian,
+            "ms" => Malay,
+            "ml" => Malayalam,
+            "mt" => Maltese,
+            "mr" => Marathi,
+            "ne" => Nepali,
+            "no" => Norwegian,
+            "or" => Oriya,
+            "pl" => Polish,
+            "pt" => Portuguese,
+            "pa" => Punjabi,
+            "rm" => Rhaetoromanic,
+            "ro" => Romanian,
+            "ru" => Russian,
+            "se" => Sami,
+            "sa" => Sanskrit,
+            "sr" => Serbian,
+            "sk" => Slovak,
+            "sl" => Slovenian,
+            "hsb" => Sorbian,
+            "es" => Spanish,
+            "st" => Sutu,
+            "sw" => Swahili,
+            "sv" => Swedish,
+            "ta" => Tamil,
+            "tt" => Tatar,
+            "te" => Telugu,
+            "th" => Thai,
+            "ts" => Tsonga,
+            "tn" => Tswana,
+            "tr" => Turkish,
+            "uk" => Ukrainian,
+            "ur" => Urdu,
+            "uz" => Uzbek,
+            "vi" => Vietnamese,
+            "xh" => Xhosa,
+            "zu" => Zulu,
+            "iw" => Hebrew,
+            "nb" | "nn" => Norwegian,
+            _ => Unknown,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    /// Formats the language as its English name, which `from_str` parses back
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
+
+impl FromStr for Language {
+    type Err = Infallible;
+
+    /// Parses a language from its uppercase name such as `ENGLISH` or a BCP 47 tag such
+    /// as `en`. Names are matched case insensitively, unrecognized input is `Unknown`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use Language::*;
+        Ok(match s.to_ascii_uppercase().as_str() {
+            "NEUTRAL" => Neutral,
+            "AFRIKAANS" => Afrikaans,
+            "ALBANIAN" => Albanian,
+            "ARABIC" => Arabic,
+            "ARMENIAN" => Armenian,
+            "ASSAMESE" => Assamese,
+            "AZERI" => Azeri,
+            "BASQUE" => Basque,
+            "BELARUSIAN" => Belarusian,
+            "BENGALI" => Bengali,
+            "BULGARIAN" => Bulgarian,
+            "CATALAN" => Catalan,
+            "CHINESE" => Chinese,
+            "CZECH" => Czech,
+            "DANISH" => Danish,
+            "DUTCH" => Dutch,
+            "ENGLISH" => English,
+            "ESTONIAN" => Estonian,
+            "FAEROESE" => Faeroese,
+            "FARSI" => Farsi,
+            "FINNISH" => Finnish,
+            "FRENCH" => French,
+            "GEORGIAN" => Georgian,
+            "GERMAN" => German,
+            "GREEK" => Greek,
+            "GUJARATI" => Gujarati,
+            "HEBREW" => Hebrew,
+            "HINDI" => Hindi,
+            "HUNGARIAN" => Hungarian,
+            "ICELANDIC" => Icelandic,
+            "INDONESIAN" => Indonesian,
+            "ITALIAN" => Italian,
+            "JAPANESE" => Japanese,
+            "KANNADA" => Kannada,
+            "KAZAK" => Kazak,
+            "KONKANI" => Konkani,
+            "KOREAN" => Korean,
+            "LATVIAN" => Latvian,
+            "LITHUANIAN" => Lithuanian,
+            "MACEDONIAN" => Macedonian,
+            "MALAY" => Malay,
+            "MALAYALAM" => Malayalam,
+            "MALTESE" => Maltese,
+            "MARATHI" => Marathi,
+            "NEPALI" => Nepali,
+            "NORWEGIAN" => Norwegian,
+            "ORIYA" => Oriya,
+            "POLISH" => Polish,
+            "PORTUGUESE" => Portuguese,
+            "PUNJABI" => Punjabi,
+            "RHAETOROMANIC" => Rhaetoromanic,
+            "ROMANIAN" => Romanian,
+            "RUSSIAN" => Russian,
+            "SAMI" => Sami,
+            "SANSKRIT" => Sanskrit,
+            "SERBIAN" => Serbian,
+            "SLOVAK" => Slovak,
+            "SLOVENIAN" => Slovenian,
+            "SORBIAN" => Sorbian,
+            "SPANISH" => Spanish,
+            "SUTU" => Sutu,
+            "SWAHILI" => Swahili,
+            "SWEDISH" => Swedish,
+            "TAMIL" => Tamil,
+            "TATAR" => Tatar,
+            "TELUGU" => Telugu,
+            "THAI" => Thai,
+            "TSONGA" => Tsonga,
+            "TSWANA" => Tswana,
+            "TURKISH" => Turkish,
+            "UKRAINIAN" => Ukrainian,
+            "URDU" => Urdu,
+            "UZBEK" => Uzbek,
+            "VIETNAMESE" => Vietnamese,
+            "XHOSA" => Xhosa,
+            "ZULU" => Zulu,
+            _ => Language::from_bcp47(s),
+        })
+    }
+}
+
 impl From<u8> for Language {
     fn from(code: u8) -> Self {
         use Language::*;
@@ -658,12 +1080,6 @@ impl From<Language> for u8 {
     }
 }
 
-impl Default for Language {
-    fn default() -> Self {
-        Language::Neutral
-    }
-}
-
 impl WriteBeBytes for Language {
     fn write_be_bytes<W: io::Write>(&self, writer: &mut W) -> io::Result<usize> {
         u8::from(*self).write_be_bytes(writer)
@@ -672,7 +1088,7 @@ impl WriteBeBytes for Language {
 
 #[cfg(test)]
 mod tests {
-    use super::{Language, MobiHeader, MobiType, TextEncoding};
+    use super::{DrmInfo, Language, MobiFormat, MobiHeader, MobiType, TextEncoding};
     use crate::book;
     use crate::writer::Writer;
 
@@ -745,6 +1161,33 @@ mod tests {
         assert!(mobiheader.has_drm());
     }
 
+    #[test]
+    fn test_drm_info() {
+        let mobiheader = MobiHeader {
+            drm_offset: 1,
+            drm_count: 2,
+            drm_size: 3,
+            drm_flags: 4,
+            ..Default::default()
+        };
+        assert_eq!(
+            mobiheader.drm_info(),
+            Some(DrmInfo {
+                offset: 1,
+                count: 2,
+                size: 3,
+                flags: 4
+            })
+        );
+
+        let mobiheader = MobiHeader {
+            drm_offset: 0xFFFF_FFFF,
+            drm_count: 2,
+            ..Default::default()
+        };
+        assert_eq!(mobiheader.drm_info(), None);
+    }
+
     #[test]
     fn test_no_drm() {
         let mobiheader = MobiHeader {
@@ -755,6 +1198,103 @@ mod tests {
         assert!(!mobiheader.has_drm());
     }
 
+    #[test]
+    fn test_extra_record_data_flags() {
+        let mobiheader =
+            MobiHeader::parse(&mut book::u8_reader(book::MOBIHEADER.to_vec())).unwrap();
+
+        assert_eq!(mobiheader.extra_record_data_flags(), 7);
+        assert_eq!(mobiheader.extra_record_data_flags() & 1, 1);
+    }
+
+    #[test]
+    fn language_bcp47() {
+        for (language, tag) in [
+            (Language::English, "en"),
+            (Language::German, "de"),
+            (Language::Chinese, "zh"),
+        ] {
+            assert_eq!(language.to_bcp47(), tag);
+            assert_eq!(Language::from_bcp47(tag), language);
+        }
+        assert_eq!(Language::from_bcp47("zh-Hant-TW"), Language::Chinese);
+        assert_eq!(Language::from_bcp47("EN_gb"), Language::English);
+        assert_eq!(Language::from_bcp47("und"), Language::Neutral);
+        assert_eq!(Language::from_bcp47("tlh"), Language::Unknown);
+        assert_eq!(Language::from_bcp47(""), Language::Unknown);
+        assert_eq!(Language::Unknown.to_bcp47(), "und");
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(MobiType::MobiPocketBook.to_string(), "MobiPocket Book");
+        assert_eq!(MobiType::NewsFeed.to_string(), "News Feed");
+        assert_eq!(MobiType::HTML.to_string(), "HTML");
+        assert_eq!(Language::English.to_string(), "English");
+        assert_eq!(format!("{}", Language::Rhaetoromanic), "Rhaetoromanic");
+        assert_eq!(
+            Language::Rhaetoromanic.to_string().parse(),
+            Ok(Language::Rhaetoromanic)
+        );
+    }
+
+    #[test]
+    fn language_from_str() {
+        assert_eq!("ENGLISH".parse(), Ok(Language::English));
+        assert_eq!("German".parse(), Ok(Language::German));
+        assert_eq!("rhaetoromanic".parse(), Ok(Language::Rhaetoromanic));
+        assert_eq!("en".parse(), Ok(Language::English));
+        assert_eq!("pt-BR".parse(), Ok(Language::Portuguese));
+        assert_eq!("Klingon".parse(), Ok(Language::Unknown));
+        assert_eq!("".parse(), Ok(Language::Unknown));
+    }
+
+    #[test]
+    fn test_dictionary_languages() {
+        let mobiheader = MobiHeader {
+            input_language: 0x0409,
+            output_language: 7,
+            ..Default::default()
+        };
+
+        assert_eq!(mobiheader.input_language_parsed(), Language::English);
+        assert_eq!(mobiheader.output_language_parsed(), Language::German);
+    }
+
+    #[test]
+    fn test_text_encoding_name_and_codepage() {
+        assert_eq!(TextEncoding::CP1252.name(), "CP1252");
+        assert_eq!(TextEncoding::CP1252.codepage(), 1252);
+        assert_eq!(TextEncoding::UTF8.name(), "UTF-8");
+        assert_eq!(TextEncoding::UTF8.codepage(), 65001);
+        assert_eq!(TextEncoding::Unknown(1250).name(), "Unknown");
+        assert_eq!(TextEncoding::Unknown(1250).codepage(), 1250);
+    }
+
+    #[test]
+    fn test_is_periodical() {
+        let periodicals = [257, 258, 259];
+        for ty in [2, 3, 4, 257, 258, 259, 513, 514, 515, 516, 517, 518, 0] {
+            assert_eq!(
+                MobiType::from(ty).is_periodical(),
+                periodicals.contains(&ty)
+            );
+        }
+    }
+
+    #[test]
+    fn test_format() {
+        let mut mobiheader =
+            MobiHeader::parse(&mut book::u8_reader(book::MOBIHEADER.to_vec())).unwrap();
+        assert_eq!(mobiheader.format(), MobiFormat::Mobi6);
+
+        mobiheader.format_version = 8;
+        assert_eq!(mobiheader.format(), MobiFormat::Kf8);
+
+        mobiheader.format_version = 5;
+        assert_eq!(mobiheader.format(), MobiFormat::Other(5));
+    }
+
     #[test]
     fn test_write() {
         let input_bytes = book::MOBIHEADER.to_vec();
@@ -4,9 +4,9 @@ pub(crate) mod mobih;
 pub(crate) mod palmdoch;
 
 pub use self::{
-    exth::{ExtHeader, ExthRecord},
-    header::{Header, HeaderParseError},
-    mobih::{Language, MobiHeader, MobiType, TextEncoding},
+    exth::{CdeType, CreatorSoftware, ExtHeader, ExthRecord},
+    header::{Header, HeaderAttributes, HeaderParseError, Timestamps},
+    mobih::{DrmInfo, Language, MobiFormat, MobiHeader, MobiType, TextEncoding},
     palmdoch::{Compression, Encryption, PalmDocHeader},
 };
 
@@ -16,12 +16,20 @@ use crate::record::{PdbRecordParseError, PdbRecords};
 use crate::{Reader, Writer};
 
 #[cfg(feature = "time")]
-use chrono::NaiveDateTime;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use std::convert::TryFrom;
 use std::fs::File;
-use std::io::{self, BufReader, Read};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 use thiserror::Error;
 
+/// First record of a PalmDOC book shorter than this can't contain a MOBI header
+/// after the 16 byte PalmDOC header
+const PALMDOC_ONLY_MAX_LEN: u32 = 24;
+/// EXTH positions of the series name and index
+const SERIES_NAME_RECORD: u32 = 527;
+const SERIES_INDEX_RECORD: u32 = 528;
+
 #[derive(Debug, Error)]
 pub enum MetadataParseError {
     #[error(transparent)]
@@ -38,8 +46,22 @@ pub enum MetadataParseError {
     NoRecords,
 }
 
-#[derive(Debug, Default)]
-/// Holds all headers containing low level metadata of a mobi book
+#[derive(Debug, Error)]
+/// Non fatal error encountered while leniently parsing a book
+pub enum ParseWarning {
+    #[error("EXTH header skipped - {0}")]
+    ExtHeader(ExthRecordParseError),
+    #[error("book name skipped - {0}")]
+    Name(io::Error),
+    #[error("content truncated - {0}")]
+    Content(io::Error),
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+/// Holds all headers containing low level metadata of a mobi book.
+///
+/// Equality compares all headers field by field, EXTH records are compared
+/// regardless of the order in which they appear in the book.
 pub struct MobiMetadata {
     pub name: Vec<u8>,
     pub header: Header,
@@ -47,6 +69,9 @@ pub struct MobiMetadata {
     pub palmdoc: PalmDocHeader,
     pub mobi: MobiHeader,
     pub exth: ExtHeader,
+    /// Bytes between the end of headers and the book name, kept so that writing
+    /// the metadata reproduces the original file.
+    pub exth_padding: Vec<u8>,
 }
 impl MobiMetadata {
     /// Construct a Metadata object from a slice of bytes
@@ -60,13 +85,61 @@ impl MobiMetadata {
         MobiMetadata::from_reader(&mut reader)
     }
 
-    /// Construct a Metadata object from an object that implements a Read trait
+    /// Construct a Metadata object from passed file path reading only the first record
+    /// of the book, which holds all headers. This is the fast path for indexing many
+    /// books as the content of the book is never read.
+    pub fn scan<P: AsRef<Path>>(file_path: P) -> Result<MobiMetadata, MetadataParseError> {
+        let mut file = File::open(file_path)?;
+
+        let headers_end = {
+            let mut reader = Reader::new(BufReader::new(&mut file));
+            let header = Header::parse(&mut reader)?;
+            let records = PdbRecords::new(&mut reader, header.num_records)?;
+            records
+                .records
+                .get(1)
+                .map_or(u64::MAX, |record| u64::from(record.offset))
+        };
+
+        file.seek(SeekFrom::Start(0))?;
+        MobiMetadata::from_reader(&mut Reader::new(BufReader::new(file.take(headers_end))))
+    }
+
+    /// Construct a Metadata object from an object that implements a Read trait.
+    /// Reading stops right after the book name in the first record, the rest of the
+    /// stream is never consumed, so reading metadata of a large piped book is cheap.
+    /// Pass the reader by reference to keep using it afterwards.
     pub fn from_read<R: Read>(reader: R) -> Result<MobiMetadata, MetadataParseError> {
         MobiMetadata::from_reader(&mut Reader::new(reader))
     }
 
+    /// Construct a Metadata object from an object that implements a Read trait.
+    /// Unlike `from_read` this skips malformed EXTH records instead of failing and
+    /// leaves the name empty if it can't be read, `title` then falls back to EXTH.
+    pub fn from_reader_lenient<R: Read>(reader: R) -> Result<MobiMetadata, MetadataParseError> {
+        MobiMetadata::from_reader_with(&mut Reader::new(reader), true, None)
+    }
+
     pub(crate) fn from_reader<R: Read>(
         reader: &mut Reader<R>,
+    ) -> Result<MobiMetadata, MetadataParseError> {
+        MobiMetadata::from_reader_with(reader, false, None)
+    }
+
+    /// Parses metadata leniently, errors in EXTH header and book name are collected
+    /// into warnings and the affected fields are left empty. Only errors in the PDB,
+    /// PalmDOC and MOBI headers are returned.
+    pub(crate) fn from_reader_recovering<R: Read>(
+        reader: &mut Reader<R>,
+        warnings: &mut Vec<ParseWarning>,
+    ) -> Result<MobiMetadata, MetadataParseError> {
+        MobiMetadata::from_reader_with(reader, true, Some(warnings))
+    }
+
+    fn from_reader_with<R: Read>(
+        reader: &mut Reader<R>,
+        lenient: bool,
+        warnings: Option<&mut Vec<ParseWarning>>,
     ) -> Result<MobiMetadata, MetadataParseError> {
         let header = Header::parse(reader)?;
 
@@ -76,28 +149,54 @@ impl MobiMetadata {
         }
 
         let palmdoc = PalmDocHeader::parse(reader)?;
-        let mobi = MobiHeader::parse(reader)?;
-
-        let exth = if mobi.has_exth_header() {
-            ExtHeader::parse(reader)?
+        // Plain PalmDOC books have no MOBI header, their first record is too small
+        // to hold one and the text follows in the next records
+        let is_palmdoc = header.is_palmdoc()
+            && records.records.get(1).is_some_and(|record| {
+                record.offset.saturating_sub(records.records[0].offset) < PALMDOC_ONLY_MAX_LEN
+            });
+        let mobi = if is_palmdoc {
+            if usize::from(palmdoc.record_count) >= records.records.len() {
+                return Err(HeaderParseError::NotMobiButPalmDoc.into());
+            }
+            MobiHeader::palmdoc(palmdoc.record_count)
         } else {
-            ExtHeader::default()
+            match MobiHeader::parse(reader) {
+                Err(_) if header.is_palmdoc() => {
+                    return Err(HeaderParseError::NotMobiButPalmDoc.into())
+                }
+                mobi => mobi?,
+            }
         };
 
-        let name_offset = match records.records[0].offset.checked_add(mobi.name_offset) {
-            None => {
-                return Err(MetadataParseError::IoError(io::Error::new(
-                    io::ErrorKind::Other,
-                    "attempted to seek with overflow",
-                )))
-            }
-            Some(offset) => offset,
+        let exth = if !mobi.has_exth_header() {
+            Ok(ExtHeader::default())
+        } else if lenient {
+            ExtHeader::parse_lenient(reader)
+        } else {
+            ExtHeader::parse(reader)
+        };
+        let name = if is_palmdoc {
+            let name = header.name.split(|&b| b == 0).next().unwrap_or_default();
+            Ok((vec![], name.to_vec()))
+        } else {
+            Self::read_name(reader, &records, &mobi)
         };
 
-        // NOTE: The name should appear in the first record, and the first record should
-        // begin AFTER the EXTHeader
-        reader.set_position(name_offset as usize)?;
-        let name = reader.read_vec_header(mobi.name_length as usize)?;
+        let (exth, (exth_padding, name)) = match warnings {
+            None if lenient => (exth?, name.unwrap_or_default()),
+            None => (exth?, name?),
+            Some(warnings) => (
+                exth.unwrap_or_else(|e| {
+                    warnings.push(ParseWarning::ExtHeader(e));
+                    ExtHeader::default()
+                }),
+                name.unwrap_or_else(|e| {
+                    warnings.push(ParseWarning::Name(e));
+                    (vec![], vec![])
+                }),
+            ),
+        };
 
         Ok(MobiMetadata {
             name,
@@ -106,9 +205,32 @@ impl MobiMetadata {
             palmdoc,
             mobi,
             exth,
+            exth_padding,
         })
     }
 
+    fn read_name<R: Read>(
+        reader: &mut Reader<R>,
+        records: &PdbRecords,
+        mobi: &MobiHeader,
+    ) -> io::Result<(Vec<u8>, Vec<u8>)> {
+        let name_offset = records.records[0]
+            .offset
+            .checked_add(mobi.name_offset)
+            .ok_or_else(|| io::Error::other("attempted to seek with overflow"))?;
+
+        // NOTE: The name should appear in the first record, and the first record should
+        // begin AFTER the EXTHeader
+        let padding = match (name_offset as usize).checked_sub(reader.position()) {
+            Some(len) => reader.read_vec_header(len)?,
+            None => {
+                reader.set_position(name_offset as usize)?;
+                vec![]
+            }
+        };
+        Ok((padding, reader.read_vec_header(mobi.name_length as usize)?))
+    }
+
     #[allow(dead_code)]
     fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
         self.write_into(&mut Writer::new(writer))
@@ -117,16 +239,53 @@ impl MobiMetadata {
     pub(crate) fn write_into<W: io::Write>(&self, w: &mut Writer<W>) -> io::Result<()> {
         self.header.write(w, self.records.num_records())?;
         self.records.write(w)?;
+        self.write_headers_record(w)
+    }
+
+    /// Writes the first record made of all headers followed by the book name. Name offset
+    /// is moved forward if the headers no longer fit before the original name.
+    pub(crate) fn write_headers_record<W: io::Write>(&self, w: &mut Writer<W>) -> io::Result<()> {
+        let mut mobi = self.mobi.clone();
+        let mut headers = vec![];
+        self.write_headers(&mut Writer::new(&mut headers), &mobi)?;
+        if headers.len() > mobi.name_offset as usize {
+            mobi.name_offset = headers.len() as u32;
+            headers.clear();
+            self.write_headers(&mut Writer::new(&mut headers), &mobi)?;
+        }
+        w.write_be(headers.as_slice())?;
+
+        let fill = mobi.name_offset as usize - headers.len();
+        // Reuse original padding, zero filling if the headers got shorter
+        let padding = &self.exth_padding[..fill.min(self.exth_padding.len())];
+        w.write_be(padding)?;
+        w.write_be(vec![0; fill - padding.len()])?;
+        w.write_be(self.name.as_slice())
+    }
+
+    fn write_headers<W: io::Write>(&self, w: &mut Writer<W>, mobi: &MobiHeader) -> io::Result<()> {
         self.palmdoc.write(w)?;
-        self.mobi.write(w)?;
-        if self.mobi.has_exth_header() {
+        mobi.write(w)?;
+        if mobi.has_exth_header() {
             self.exth.write(w)?;
         }
+        Ok(())
+    }
 
-        let fill = ((self.records.records[0].offset + self.mobi.name_offset) as usize)
-            .saturating_sub(w.bytes_written());
-        w.write_be(vec![0; fill])?;
-        w.write_be(self.name.as_slice())
+    fn ensure_exth_header(&mut self) {
+        if !self.mobi.has_exth_header() {
+            self.mobi.enable_exth_header();
+            self.exth.identifier = u32::from_be_bytes(*b"EXTH");
+        }
+    }
+
+    /// Points the cover offset record at passed offset relative to the first image
+    /// record, enabling the EXTH header if the book has none.
+    pub(crate) fn set_cover_offset(&mut self, offset: u32) {
+        self.ensure_exth_header();
+        self.exth
+            .records
+            .insert(ExthRecord::CoverOffset, vec![offset.to_be_bytes().to_vec()]);
     }
 
     //################################################################################//
@@ -155,6 +314,161 @@ impl MobiMetadata {
         self.exth.get_record_position(position)
     }
 
+    /// Returns all EXTH records with their numeric positions and raw data buffers.
+    ///
+    /// This is useful when inspecting records not known to this library as their positions
+    /// are preserved.
+    pub fn exth_positions(&self) -> Vec<(u32, Vec<Vec<u8>>)> {
+        self.exth
+            .raw_records()
+            .map(|(r, data)| (r.position(), data.clone()))
+            .collect()
+    }
+
+    /// Returns every value of all EXTH records lossily converted to a string, in the
+    /// order they were read.
+    pub fn all_metadata(&self) -> Vec<(ExthRecord, String)> {
+        self.exth
+            .iter_in_order()
+            .map(|(position, data)| {
+                (
+                    ExthRecord::from(position),
+                    String::from_utf8_lossy(data).to_string(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns EXTH records not known to this library with their numeric positions.
+    pub fn unknown_exth_records(&self) -> Vec<(u32, &Vec<Vec<u8>>)> {
+        self.exth
+            .raw_records()
+            .filter_map(|(r, data)| match r {
+                ExthRecord::Other(position) => Some((*position, data)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns absolute index of the cover image record resolved from the cover offset
+    /// record relative to the first image record. Returns `None` if the record doesn't
+    /// exist or is shorter than 4 bytes.
+    pub fn cover_record_index(&self) -> Option<usize> {
+        self.image_record_index(ExthRecord::CoverOffset)
+    }
+
+    /// Returns absolute index of the thumbnail image record resolved from the thumbnail
+    /// offset record relative to the first image record. Returns `None` if the record
+    /// doesn't exist or is shorter than 4 bytes.
+    pub fn thumbnail_record_index(&self) -> Option<usize> {
+        self.image_record_index(ExthRecord::ThumbOffset)
+    }
+
+    fn image_record_index(&self, record: ExthRecord) -> Option<usize> {
+        let offset = self.exth.get_record_u32(record)?;
+        (self.mobi.first_image_index as usize).checked_add(offset as usize)
+    }
+
+    /// Returns version number record if such exists. Returns `None` if the record
+    /// is shorter than 4 bytes.
+    pub fn version_number(&self) -> Option<u32> {
+        self.exth.get_record_u32(ExthRecord::VersionNumber)
+    }
+
+    /// Returns watermark record if such exists
+    pub fn watermark(&self) -> Option<String> {
+        self.exth.get_record_string_lossy(ExthRecord::Watermark)
+    }
+
+    /// Removes EXTH records holding store specific data that can identify the owner
+    /// of this book: watermark, tamper proof keys and font signature.
+    pub fn scrub_personal_data(&mut self) {
+        for record in [
+            ExthRecord::Watermark,
+            ExthRecord::TamperProofKeys,
+            ExthRecord::FontSignature,
+        ] {
+            self.exth.records.shift_remove(&record);
+        }
+    }
+
+    /// Copies listed EXTH records from `other`, overwriting values of records already
+    /// present. Records missing from `other` are left untouched.
+    pub fn merge_from(&mut self, other: &MobiMetadata, fields: &[ExthRecord]) {
+        for &field in fields {
+            if let Some(data) = other.exth.get_record(field) {
+                self.ensure_exth_header();
+                self.exth.records.insert(field, data.clone());
+            }
+        }
+    }
+
+    /// Returns true if the cover of this book is a generated placeholder
+    pub fn has_fake_cover(&self) -> bool {
+        self.exth
+            .get_record_u32(ExthRecord::HasFakeCover)
+            .is_some_and(|fake| fake != 0)
+    }
+
+    /// Returns true if this book is flagged as adult only
+    pub fn is_adult(&self) -> bool {
+        self.exth
+            .get_record(ExthRecord::Adult)
+            .and_then(|r| r.first())
+            .is_some_and(|adult| adult.eq_ignore_ascii_case(b"yes"))
+    }
+
+    /// Returns clipping limit record, the percentage of text that can be clipped, if such
+    /// exists. The limit is usually stored in a single byte but 4 byte values are accepted too.
+    pub fn clipping_limit(&self) -> Option<u8> {
+        let data = self.exth.get_record(ExthRecord::ClippingLimit)?.first()?;
+        match data.as_slice() {
+            [limit] => Some(*limit),
+            _ => self
+                .exth
+                .get_record_u32(ExthRecord::ClippingLimit)
+                .and_then(|limit| u8::try_from(limit).ok()),
+        }
+    }
+
+    /// Returns count of resources record, the number of resource records, if such exists.
+    /// Returns `None` if the record is shorter than 4 bytes.
+    pub fn resource_count(&self) -> Option<u32> {
+        self.exth.get_record_u32(ExthRecord::CountOfResources)
+    }
+
+    /// Returns KF8 cover URI record, a `kindle:embed:` URI of the cover resource,
+    /// if such exists.
+    pub fn kf8_cover_uri(&self) -> Option<String> {
+        self.exth.get_record_string_lossy(ExthRecord::KF8CoverURI)
+    }
+
+    /// Returns a hash of title, author, ISBN and text length of this book for finding
+    /// duplicates. The hash is stable across builds and platforms.
+    pub fn content_hash(&self) -> u64 {
+        // 64 bit FNV-1a
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |bytes: &[u8]| {
+            for b in bytes {
+                hash ^= u64::from(*b);
+                hash = hash.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        for field in [Some(self.title()), self.author(), self.isbn()] {
+            match field {
+                Some(field) => {
+                    write(&[1]);
+                    write(field.as_bytes());
+                }
+                None => write(&[0]),
+            }
+            // Separator that doesn't occur in UTF-8
+            write(&[0xFF]);
+        }
+        write(&self.palmdoc.text_length.to_be_bytes());
+        hash
+    }
+
     //################################################################################//
     // Available in Mobi
 
@@ -163,12 +477,23 @@ impl MobiMetadata {
         self.exth.get_record_string_lossy(exth::ExthRecord::Author)
     }
 
+    /// Returns all authors of this book
+    pub fn authors(&self) -> Vec<String> {
+        self.exth.get_record_strings_lossy(exth::ExthRecord::Author)
+    }
+
     /// Returns this books publisher
     pub fn publisher(&self) -> Option<String> {
         self.exth
             .get_record_string_lossy(exth::ExthRecord::Publisher)
     }
 
+    /// Returns all publishers of this book
+    pub fn publishers(&self) -> Vec<String> {
+        self.exth
+            .get_record_strings_lossy(exth::ExthRecord::Publisher)
+    }
+
     /// Returns description record if such exists
     pub fn description(&self) -> Option<String> {
         self.exth
@@ -186,19 +511,113 @@ impl MobiMetadata {
             .get_record_string_lossy(exth::ExthRecord::PublishDate)
     }
 
+    /// Returns the series name and index read from EXTH records 527 and 528. Series
+    /// are not part of the format, these positions are used by tools writing them.
+    /// KF8 books store the page progression direction at 527, such values are not
+    /// treated as a series. Index is `None` if it isn't a number.
+    pub fn series(&self) -> Option<(String, Option<f32>)> {
+        let name = self
+            .exth
+            .get_record_string_lossy(exth::ExthRecord::Other(SERIES_NAME_RECORD))?;
+        if ["ltr", "rtl", "default"].contains(&name.as_str()) {
+            return None;
+        }
+        let index = self
+            .exth
+            .get_record_string_lossy(exth::ExthRecord::Other(SERIES_INDEX_RECORD))
+            .and_then(|index| index.trim().parse().ok());
+        Some((name, index))
+    }
+
+    #[cfg(feature = "time")]
+    /// Returns publish_date record parsed as a datetime. Returns `None` if the record
+    /// doesn't exist or isn't in one of the common ISO-8601 forms.
+    /// This field is only available using `time` feature
+    pub fn published_datetime(&self) -> Option<NaiveDateTime> {
+        self.publish_date().as_deref().and_then(parse_datetime)
+    }
+
+    /// Returns last_update_time record if such exists
+    pub fn last_update_time(&self) -> Option<String> {
+        self.exth
+            .get_record_string_lossy(exth::ExthRecord::LastUpdateTime)
+    }
+
+    #[cfg(feature = "time")]
+    /// Returns last_update_time record parsed as a datetime. Returns `None` if the record
+    /// doesn't exist or isn't in one of the common ISO-8601 forms.
+    /// This field is only available using `time` feature
+    pub fn last_update_datetime(&self) -> Option<NaiveDateTime> {
+        self.last_update_time().as_deref().and_then(parse_datetime)
+    }
+
+    /// Returns the type of this document read from cdetype record if such exists
+    pub fn cde_type(&self) -> Option<CdeType> {
+        self.exth_record(ExthRecord::Cdetype)
+            .and_then(|r| r.first())
+            .map(|r| CdeType::from(r.as_slice()))
+    }
+
+    /// Returns information about the software that created this book if the creator
+    /// software record exists. Beware that Calibre fakes these values.
+    pub fn creator_software(&self) -> Option<CreatorSoftware> {
+        self.exth
+            .get_record_u32(ExthRecord::CreatorSoftware)
+            .map(|id| CreatorSoftware {
+                id,
+                major_version: self.exth.get_record_u32(ExthRecord::CreatoreMajorVersion),
+                minor_version: self.exth.get_record_u32(ExthRecord::CreatorMinorVersion),
+                build_number: self.exth.get_record_u32(ExthRecord::CreatorBuildNumber),
+            })
+    }
+
     /// Returns contributor record if such exists
     pub fn contributor(&self) -> Option<String> {
         self.exth
             .get_record_string_lossy(exth::ExthRecord::Contributor)
     }
 
+    /// Returns dictionary short name record if such exists. Only dictionaries
+    /// contain this record.
+    pub fn dictionary_short_name(&self) -> Option<String> {
+        self.exth
+            .get_record_string_lossy(exth::ExthRecord::DictionaryShortName)
+    }
+
+    /// Returns retail price record along with the retail price currency record if
+    /// such exist. Returns `None` only if the price record is missing.
+    pub fn retail_price(&self) -> Option<(String, Option<String>)> {
+        self.exth
+            .get_record_string_lossy(exth::ExthRecord::RetailPrice)
+            .map(|price| {
+                (
+                    price,
+                    self.exth
+                        .get_record_string_lossy(exth::ExthRecord::RetailPriceCurrency),
+                )
+            })
+    }
+
     /// Returns title record read from EXTH header if it exists
     /// or defaults to full book name read from location specified
     /// in MOBI header.
     pub fn title(&self) -> String {
-        self.exth
-            .get_record_string_lossy(exth::ExthRecord::Title)
-            .map_or(String::from_utf8_lossy(&self.name).to_string(), |v| v)
+        self.title_opt()
+            .unwrap_or_else(|| String::from_utf8_lossy(&self.name).to_string())
+    }
+
+    /// Returns title record read from EXTH header if it exists. Unlike `title`
+    /// this doesn't fall back to the book name from MOBI header.
+    pub fn title_opt(&self) -> Option<String> {
+        self.exth.get_record_string_lossy(exth::ExthRecord::Title)
+    }
+
+    /// Returns the database name from PDB header with trailing NUL padding removed.
+    /// Unlike `name` that holds the full book name this is limited to 32 bytes.
+    pub fn pdb_name(&self) -> String {
+        String::from_utf8_lossy(&self.header.name)
+            .trim_end_matches('\0')
+            .to_string()
     }
 
     /// Returns text encoding used in ebook
@@ -211,11 +630,43 @@ impl MobiMetadata {
         self.mobi.mobi_type()
     }
 
+    /// Checks if this ebook is a periodical
+    pub fn is_periodical(&self) -> bool {
+        self.mobi.mobi_type().is_periodical()
+    }
+
+    /// Returns the version of the MOBI format used by this ebook
+    pub fn format(&self) -> MobiFormat {
+        self.mobi.format()
+    }
+
     /// Returns language of the ebook
     pub fn language(&self) -> Language {
         self.mobi.language()
     }
 
+    /// Returns input and output languages of a dictionary. Returns `None` if neither
+    /// is set which is the case for books that aren't dictionaries.
+    pub fn dictionary_languages(&self) -> Option<(Language, Language)> {
+        if self.mobi.input_language == 0 && self.mobi.output_language == 0 {
+            return None;
+        }
+        Some((
+            self.mobi.input_language_parsed(),
+            self.mobi.output_language_parsed(),
+        ))
+    }
+
+    /// Returns unique id of this ebook
+    pub fn book_id(&self) -> u32 {
+        self.mobi.id
+    }
+
+    /// Returns version of the generator that created this ebook
+    pub fn generator_version(&self) -> u32 {
+        self.mobi.gen_version
+    }
+
     #[cfg(feature = "time")]
     /// Returns creation datetime
     /// This field is only available using `time` feature
@@ -242,6 +693,12 @@ impl MobiMetadata {
         self.header.mod_datetime()
     }
 
+    /// Returns raw creation and last modification timestamps regardless of
+    /// the `time` feature
+    pub fn timestamps(&self) -> Timestamps {
+        self.header.timestamps()
+    }
+
     /// Returns compression method used on this file
     pub fn compression(&self) -> Compression {
         self.palmdoc.compression()
@@ -252,6 +709,21 @@ impl MobiMetadata {
         self.palmdoc.encryption()
     }
 
+    /// Checks if there is DRM on this book
+    pub fn has_drm(&self) -> bool {
+        self.mobi.has_drm()
+    }
+
+    /// Returns DRM descriptor if there is DRM on this book
+    pub fn drm_info(&self) -> Option<DrmInfo> {
+        self.mobi.drm_info()
+    }
+
+    /// Returns the size of the whole book text in bytes before decompression and decoding
+    pub fn uncompressed_text_length(&self) -> u32 {
+        self.palmdoc.text_length
+    }
+
     /// Returns a list of subject records as a string if such records exist
     pub fn subjects(&self) -> Option<Vec<String>> {
         self.exth_record(ExthRecord::Subject).map(|s| {
@@ -262,11 +734,68 @@ impl MobiMetadata {
     }
 }
 
+#[cfg(feature = "time")]
+/// Parses datetimes stored as text in EXTH records like `2010-12-21T00:00:00+00:00`
+/// or just `2010-12-21`.
+fn parse_datetime(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S"))
+        .ok()
+        .or_else(|| {
+            NaiveDate::parse_from_str(s, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::book;
 
+    #[test]
+    fn test_palmdoc_book() {
+        let m = MobiMetadata::new(book::palmdoc_book(&[b"Hello"])).unwrap();
+        assert_eq!(m.title(), "Lord_of_the_Rings_-_Fellowship_");
+        assert_eq!(m.text_encoding(), TextEncoding::CP1252);
+        assert_eq!(m.mobi.first_non_book_index, 2);
+        assert_eq!(m.exth, ExtHeader::default());
+
+        let mut bytes = book::palmdoc_book(&[b"Hello"]);
+        // Declare more text records than there are
+        bytes[104..106].copy_from_slice(&5u16.to_be_bytes());
+        assert!(matches!(
+            MobiMetadata::new(&bytes),
+            Err(MetadataParseError::HeaderParseError(
+                HeaderParseError::NotMobiButPalmDoc
+            ))
+        ));
+    }
+
+    #[test]
+    fn test_palmdoc_identifiers() {
+        let mut bytes = book::palmdoc_book(&[b"Hello"]);
+        // Real PalmDOC type and creator, declaring more text records than there are
+        bytes[60..68].copy_from_slice(b"TEXtREAd");
+        bytes[104..106].copy_from_slice(&5u16.to_be_bytes());
+        assert!(matches!(
+            MobiMetadata::new(&bytes),
+            Err(MetadataParseError::HeaderParseError(
+                HeaderParseError::NotMobiButPalmDoc
+            ))
+        ));
+
+        bytes[60..68].copy_from_slice(b"TEXTread");
+        assert!(matches!(
+            MobiMetadata::new(&bytes),
+            Err(MetadataParseError::HeaderParseError(
+                HeaderParseError::InvalidCreatorIdentifier
+            ))
+        ));
+    }
+
     #[test]
     fn test_mobi_metadata() {
         let mut reader = book::u8_reader(book::full_book());
@@ -275,10 +804,522 @@ mod test {
 
     #[test]
     fn test_mobi_write() {
-        // First write will lose duplicate ExtHeader records.
+        // EXTH records are written in the order they were read, reproducing the book
         let m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
         let mut bytes = vec![];
         assert!(m.write(&mut bytes).is_ok());
-        assert_eq!(bytes, book::MOBI_METADATA);
+        assert_eq!(bytes, book::full_book());
+    }
+
+    #[test]
+    fn test_title_opt() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(
+            m.title_opt(),
+            Some(String::from("Lord of the Rings - Fellowship of the Ring"))
+        );
+
+        m.exth.records.shift_remove(&ExthRecord::Title);
+        assert_eq!(m.title_opt(), None);
+        m.name = b"Fallback name".to_vec();
+        assert_eq!(m.title(), "Fallback name");
+    }
+
+    #[test]
+    fn test_pdb_name() {
+        let m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.pdb_name(), "Lord_of_the_Rings_-_Fellowship_");
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_published_datetime() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        let expected = NaiveDate::from_ymd_opt(2010, 12, 21)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .unwrap();
+        assert_eq!(m.published_datetime(), Some(expected));
+
+        m.exth
+            .records
+            .insert(ExthRecord::PublishDate, vec![b"2010-12-21".to_vec()]);
+        assert_eq!(m.published_datetime(), Some(expected));
+
+        m.exth
+            .records
+            .insert(ExthRecord::PublishDate, vec![b"sometime in 2010".to_vec()]);
+        assert_eq!(m.published_datetime(), None);
+    }
+
+    #[test]
+    fn test_cover_record_index() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.cover_record_index(), Some(287));
+        assert_eq!(m.thumbnail_record_index(), Some(288));
+
+        m.exth
+            .records
+            .insert(ExthRecord::CoverOffset, vec![b"\0\0\0\x05".to_vec()]);
+        m.exth
+            .records
+            .insert(ExthRecord::ThumbOffset, vec![b"\0\0".to_vec()]);
+        assert_eq!(m.cover_record_index(), Some(292));
+        assert_eq!(m.thumbnail_record_index(), None);
+
+        m.exth.records.shift_remove(&ExthRecord::CoverOffset);
+        assert_eq!(m.cover_record_index(), None);
+    }
+
+    #[test]
+    fn test_authors() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.authors(), vec!["J. R. R. Tolkien".to_string()]);
+
+        m.exth.records.insert(
+            ExthRecord::Author,
+            vec![b"Terry Pratchett".to_vec(), b"Neil Gaiman".to_vec()],
+        );
+        assert_eq!(m.author(), Some("Terry Pratchett".to_string()));
+        assert_eq!(
+            m.authors(),
+            vec!["Terry Pratchett".to_string(), "Neil Gaiman".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_publishers() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        m.exth.records.insert(
+            ExthRecord::Publisher,
+            vec![b"HarperCollins".to_vec(), b"Allen & Unwin".to_vec()],
+        );
+        assert_eq!(
+            m.publishers(),
+            vec!["HarperCollins".to_string(), "Allen & Unwin".to_string()]
+        );
+
+        m.exth.records.shift_remove(&ExthRecord::Publisher);
+        assert!(m.publishers().is_empty());
+    }
+
+    #[test]
+    fn test_dictionary_short_name() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.dictionary_short_name(), None);
+
+        m.exth.records.insert(
+            ExthRecord::DictionaryShortName,
+            vec![b"Oxford Dictionary of English".to_vec()],
+        );
+        assert_eq!(
+            m.dictionary_short_name(),
+            Some("Oxford Dictionary of English".to_string())
+        );
+    }
+
+    #[test]
+    fn test_dictionary_languages() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.dictionary_languages(), None);
+
+        m.mobi.input_language = 12;
+        assert_eq!(
+            m.dictionary_languages(),
+            Some((Language::French, Language::Neutral))
+        );
+    }
+
+    #[test]
+    fn test_retail_price() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.retail_price(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::RetailPrice, vec![b"4.99".to_vec()]);
+        assert_eq!(m.retail_price(), Some(("4.99".to_string(), None)));
+
+        m.exth
+            .records
+            .insert(ExthRecord::RetailPriceCurrency, vec![b"USD".to_vec()]);
+        assert_eq!(
+            m.retail_price(),
+            Some(("4.99".to_string(), Some("USD".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_version_number() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.version_number(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::VersionNumber, vec![vec![0, 0, 0x01, 0x02]]);
+        assert_eq!(m.version_number(), Some(258));
+
+        m.exth
+            .records
+            .insert(ExthRecord::VersionNumber, vec![vec![0x01, 0x02]]);
+        assert_eq!(m.version_number(), None);
+    }
+
+    #[test]
+    fn test_clipping_limit() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.clipping_limit(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::ClippingLimit, vec![vec![10]]);
+        assert_eq!(m.clipping_limit(), Some(10));
+
+        m.exth
+            .records
+            .insert(ExthRecord::ClippingLimit, vec![vec![0, 0, 0, 10]]);
+        assert_eq!(m.clipping_limit(), Some(10));
+    }
+
+    #[test]
+    fn test_resource_count() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.resource_count(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::CountOfResources, vec![vec![0, 0, 0, 5]]);
+        assert_eq!(m.resource_count(), Some(5));
+    }
+
+    #[test]
+    fn test_kf8_cover_uri() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.kf8_cover_uri(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::KF8CoverURI, vec![b"kindle:embed:0001".to_vec()]);
+        assert_eq!(m.kf8_cover_uri(), Some("kindle:embed:0001".to_string()));
+    }
+
+    #[test]
+    fn test_from_read_stops_after_name() {
+        struct CountingReader<'a> {
+            bytes: &'a [u8],
+            consumed: usize,
+        }
+        impl Read for CountingReader<'_> {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                let read = self.bytes[self.consumed..].as_ref().read(buf)?;
+                self.consumed += read;
+                Ok(read)
+            }
+        }
+
+        // Fixture ends right after the name, append the rest of the book
+        let mut bytes = book::full_book();
+        bytes.extend_from_slice(&[0; 4096]);
+        let mut reader = CountingReader {
+            bytes: &bytes,
+            consumed: 0,
+        };
+        let m = MobiMetadata::from_read(&mut reader).unwrap();
+        let name_end = (m.records.records[0].offset + m.mobi.name_offset) as usize + m.name.len();
+        assert_eq!(reader.consumed, name_end);
+        assert!(reader.consumed < bytes.len());
+    }
+
+    #[test]
+    fn test_from_reader_lenient() {
+        let strict = MobiMetadata::new(book::full_book()).unwrap();
+        let lenient = MobiMetadata::from_reader_lenient(book::full_book().as_slice()).unwrap();
+        assert_eq!(strict.exth, lenient.exth);
+    }
+
+    #[test]
+    fn test_from_reader_lenient_unreadable_name() {
+        let mut bytes = book::full_book();
+        // Name offset in the MOBI header of the first record
+        let name_offset = 2416 + 0x54;
+        bytes[name_offset..name_offset + 4].copy_from_slice(&0x00FF_FFFFu32.to_be_bytes());
+        assert!(MobiMetadata::new(&bytes).is_err());
+
+        let m = MobiMetadata::from_reader_lenient(bytes.as_slice()).unwrap();
+        assert_eq!(m.mobi.name_offset, 0x00FF_FFFF);
+        assert!(m.name.is_empty());
+        assert_eq!(m.title(), "Lord of the Rings - Fellowship of the Ring");
+    }
+
+    #[test]
+    fn test_series() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.series(), None);
+
+        m.exth.records.insert(
+            ExthRecord::Other(527),
+            vec![b"The Lord of the Rings".to_vec()],
+        );
+        assert_eq!(
+            m.series(),
+            Some((String::from("The Lord of the Rings"), None))
+        );
+
+        m.exth
+            .records
+            .insert(ExthRecord::Other(528), vec![b"1.5".to_vec()]);
+        assert_eq!(
+            m.series(),
+            Some((String::from("The Lord of the Rings"), Some(1.5)))
+        );
+
+        m.exth
+            .records
+            .insert(ExthRecord::Other(527), vec![b"rtl".to_vec()]);
+        assert_eq!(m.series(), None);
+    }
+
+    #[test]
+    fn test_merge_from() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        let mut other = MobiMetadata::default();
+        let subjects = vec![b"Fantasy".to_vec(), b"Classics".to_vec()];
+        other
+            .exth
+            .records
+            .insert(ExthRecord::Subject, subjects.clone());
+        other
+            .exth
+            .records
+            .insert(ExthRecord::Author, vec![b"Anon".to_vec()]);
+
+        m.merge_from(&other, &[ExthRecord::Subject, ExthRecord::Publisher]);
+        assert_eq!(m.exth.get_record(ExthRecord::Subject), Some(&subjects));
+        assert_eq!(
+            m.publisher().as_deref(),
+            Some("HarperCollins Publishers Ltd")
+        );
+        assert_eq!(m.author().as_deref(), Some("J. R. R. Tolkien"));
+
+        let mut empty = MobiMetadata::default();
+        empty.merge_from(&other, &[ExthRecord::Author]);
+        assert!(empty.mobi.has_exth_header());
+        assert_eq!(empty.author().as_deref(), Some("Anon"));
+    }
+
+    #[test]
+    fn test_equality() {
+        let m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        let mut other = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m, other);
+        assert_eq!(m.content_hash(), other.content_hash());
+
+        let author = other
+            .exth
+            .records
+            .shift_remove(&ExthRecord::Author)
+            .unwrap();
+        other.exth.records.insert(ExthRecord::Author, author);
+        assert_eq!(m, other);
+
+        other.palmdoc.text_length += 1;
+        assert_ne!(m, other);
+        assert_ne!(m.content_hash(), other.content_hash());
+    }
+
+    #[test]
+    fn test_has_fake_cover() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert!(!m.has_fake_cover());
+
+        m.exth
+            .records
+            .insert(ExthRecord::HasFakeCover, vec![vec![0, 0, 0, 1]]);
+        assert!(m.has_fake_cover());
+
+        m.exth.records.shift_remove(&ExthRecord::HasFakeCover);
+        assert!(!m.has_fake_cover());
+    }
+
+    #[test]
+    fn test_is_adult() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert!(!m.is_adult());
+
+        for (adult, expected) in [(&b"yes"[..], true), (b"YES", true), (b"no", false)] {
+            m.exth
+                .records
+                .insert(ExthRecord::Adult, vec![adult.to_vec()]);
+            assert_eq!(m.is_adult(), expected);
+        }
+    }
+
+    #[test]
+    fn test_scrub_personal_data() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.watermark(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::Watermark, vec![b"owner@example.com".to_vec()]);
+        m.exth
+            .records
+            .insert(ExthRecord::TamperProofKeys, vec![vec![1, 2, 3, 4]]);
+        m.exth
+            .records
+            .insert(ExthRecord::FontSignature, vec![vec![5, 6, 7, 8]]);
+        assert_eq!(m.watermark(), Some(String::from("owner@example.com")));
+
+        m.scrub_personal_data();
+        assert_eq!(m.watermark(), None);
+
+        let mut bytes = vec![];
+        m.write(&mut bytes).unwrap();
+        assert_eq!(bytes, book::full_book());
+        let written = MobiMetadata::new(&bytes).unwrap();
+        for record in [
+            ExthRecord::Watermark,
+            ExthRecord::TamperProofKeys,
+            ExthRecord::FontSignature,
+        ] {
+            assert_eq!(written.exth_record(record), None);
+        }
+    }
+
+    #[test]
+    fn test_scan() {
+        let path = std::env::temp_dir().join("mobi-rs-test-scan.mobi");
+        std::fs::write(&path, book::full_book()).unwrap();
+
+        let scanned = MobiMetadata::scan(&path).unwrap();
+        assert_eq!(scanned, MobiMetadata::from_path(&path).unwrap());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_write_exth_padding() {
+        let mut bytes = book::full_book();
+        let m = MobiMetadata::new(&bytes).unwrap();
+        assert!(!m.exth_padding.is_empty());
+        let name_start = (m.records.records[0].offset + m.mobi.name_offset) as usize;
+        bytes[name_start - m.exth_padding.len()..name_start].fill(0xAB);
+
+        let m = MobiMetadata::new(&bytes).unwrap();
+        assert!(m.exth_padding.iter().all(|&b| b == 0xAB));
+        let mut written = vec![];
+        m.write(&mut written).unwrap();
+        assert_eq!(written, bytes);
+    }
+
+    #[test]
+    fn test_unknown_exth_records() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert!(m.unknown_exth_records().is_empty());
+
+        m.exth
+            .records
+            .insert(ExthRecord::Other(999), vec![b"unknown".to_vec()]);
+        assert_eq!(
+            m.unknown_exth_records(),
+            vec![(999, &vec![b"unknown".to_vec()])]
+        );
+    }
+
+    #[test]
+    fn test_all_metadata() {
+        let m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        let metadata = m.all_metadata();
+
+        assert_eq!(metadata.len(), m.exth.actual_record_count());
+        assert!(metadata.contains(&(ExthRecord::Author, String::from("J. R. R. Tolkien"))));
+        assert!(metadata.contains(&(
+            ExthRecord::Publisher,
+            String::from("HarperCollins Publishers Ltd")
+        )));
+        let publish_dates = metadata
+            .iter()
+            .enumerate()
+            .filter(|(_, (record, _))| *record == ExthRecord::PublishDate)
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        // Duplicate records keep their position in the file
+        assert_eq!(publish_dates, vec![4, 7]);
+    }
+
+    #[test]
+    fn test_cde_type() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.cde_type(), None);
+
+        m.exth
+            .records
+            .insert(ExthRecord::Cdetype, vec![b"EBOK".to_vec()]);
+        assert_eq!(m.cde_type(), Some(CdeType::Ebook));
+    }
+
+    #[test]
+    fn test_creator_software() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.creator_software(), None);
+
+        m.exth.records.insert(
+            ExthRecord::CreatorSoftware,
+            vec![201u32.to_be_bytes().to_vec()],
+        );
+        m.exth.records.insert(
+            ExthRecord::CreatoreMajorVersion,
+            vec![1u32.to_be_bytes().to_vec()],
+        );
+        m.exth.records.insert(
+            ExthRecord::CreatorMinorVersion,
+            vec![2u32.to_be_bytes().to_vec()],
+        );
+        m.exth.records.insert(
+            ExthRecord::CreatorBuildNumber,
+            vec![33307u32.to_be_bytes().to_vec()],
+        );
+        assert_eq!(
+            m.creator_software(),
+            Some(CreatorSoftware {
+                id: 201,
+                major_version: Some(1),
+                minor_version: Some(2),
+                build_number: Some(33307),
+            })
+        );
+    }
+
+    #[test]
+    fn test_exth_positions() {
+        let m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        let positions = m.exth_positions();
+
+        assert_eq!(positions.len(), m.exth.records.len());
+        assert!(positions
+            .iter()
+            .any(|(p, data)| *p == 100 && data == &vec![b"J. R. R. Tolkien".to_vec()]));
+        assert!(positions.iter().any(|(p, data)| *p == 503
+            && data == &vec![b"Lord of the Rings - Fellowship of the Ring".to_vec()]));
+    }
+
+    #[test]
+    fn test_last_update_time() {
+        let mut m = MobiMetadata::from_reader(&mut book::u8_reader(book::full_book())).unwrap();
+        assert_eq!(m.last_update_time(), None);
+
+        m.exth.records.insert(
+            ExthRecord::LastUpdateTime,
+            vec![b"2011-03-09T22:32:59+00:00".to_vec()],
+        );
+        assert_eq!(
+            m.last_update_time(),
+            Some(String::from("2011-03-09T22:32:59+00:00"))
+        );
+
+        #[cfg(feature = "time")]
+        assert_eq!(
+            m.last_update_datetime(),
+            NaiveDate::from_ymd_opt(2011, 3, 9).and_then(|d| d.and_hms_opt(22, 32, 59))
+        );
     }
 }
@@ -1,28 +1,27 @@
 use crate::writer::WriteBeBytes;
 use crate::{Reader, Writer};
 
-use std::io;
+use std::{fmt, io};
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 /// Compression types available in MOBI format.
 pub enum Compression {
+    #[default]
     No,
     PalmDoc,
     Huff,
-}
-
-impl Default for Compression {
-    fn default() -> Compression {
-        Compression::No
-    }
+    /// Compression value not known to this library, content of such books is read
+    /// as uncompressed
+    Unknown(u16),
 }
 
 impl From<u16> for Compression {
     fn from(n: u16) -> Compression {
         match n {
+            1 => Compression::No,
             2 => Compression::PalmDoc,
             17480 => Compression::Huff,
-            _ => Compression::No,
+            n => Compression::Unknown(n),
         }
     }
 }
@@ -32,6 +31,18 @@ impl From<Compression> for u16 {
             Compression::No => 1,
             Compression::PalmDoc => 2,
             Compression::Huff => 17480,
+            Compression::Unknown(n) => n,
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Compression::No => f.write_str("No Compression"),
+            Compression::PalmDoc => f.write_str("PalmDOC Compression"),
+            Compression::Huff => f.write_str("HUFF/CDIC Compression"),
+            Compression::Unknown(n) => write!(f, "Unknown Compression ({})", n),
         }
     }
 }
@@ -42,17 +53,19 @@ impl WriteBeBytes for Compression {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
 /// Encryption types available in MOBI format.
 pub enum Encryption {
+    #[default]
     No,
     OldMobiPocket,
     MobiPocket,
 }
 
-impl Default for Encryption {
-    fn default() -> Self {
-        Encryption::No
+impl Encryption {
+    /// Returns true if content is encrypted with any of the DRM schemes
+    pub fn is_encrypted(&self) -> bool {
+        *self != Encryption::No
     }
 }
 
@@ -76,6 +89,16 @@ impl From<Encryption> for u16 {
     }
 }
 
+impl fmt::Display for Encryption {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Encryption::No => "No Encryption",
+            Encryption::OldMobiPocket => "Old MobiPocket Encryption",
+            Encryption::MobiPocket => "MobiPocket Encryption",
+        })
+    }
+}
+
 impl WriteBeBytes for Encryption {
     fn write_be_bytes<W: io::Write>(&self, writer: &mut W) -> io::Result<usize> {
         u16::from(*self).write_be_bytes(writer)
@@ -126,6 +149,14 @@ impl PalmDocHeader {
     pub fn encryption(&self) -> Encryption {
         self.encryption
     }
+
+    /// Returns true if the size of text records is a power of two not larger than
+    /// 4096 bytes and the compression is known. Other values hint at a corrupted header.
+    pub fn looks_valid(&self) -> bool {
+        self.record_size.is_power_of_two()
+            && self.record_size <= 4096
+            && !matches!(self.compression, Compression::Unknown(_))
+    }
 }
 
 #[cfg(test)]
@@ -133,6 +164,30 @@ mod tests {
     use super::*;
     use crate::book;
 
+    #[test]
+    fn display() {
+        assert_eq!(Compression::No.to_string(), "No Compression");
+        assert_eq!(Compression::PalmDoc.to_string(), "PalmDOC Compression");
+        assert_eq!(Compression::Huff.to_string(), "HUFF/CDIC Compression");
+        assert_eq!(
+            Compression::Unknown(0x1234).to_string(),
+            "Unknown Compression (4660)"
+        );
+        assert_eq!(Encryption::No.to_string(), "No Encryption");
+        assert_eq!(
+            Encryption::OldMobiPocket.to_string(),
+            "Old MobiPocket Encryption"
+        );
+        assert_eq!(Encryption::MobiPocket.to_string(), "MobiPocket Encryption");
+    }
+
+    #[test]
+    fn is_encrypted() {
+        assert!(!Encryption::No.is_encrypted());
+        assert!(Encryption::OldMobiPocket.is_encrypted());
+        assert!(Encryption::MobiPocket.is_encrypted());
+    }
+
     #[test]
     fn parse() {
         let pdheader = PalmDocHeader {
@@ -149,6 +204,34 @@ mod tests {
         assert_eq!(pdheader, PalmDocHeader::parse(&mut reader).unwrap());
     }
 
+    #[test]
+    fn looks_valid() {
+        let mut reader = book::u8_reader(book::PALMDOCHEADER.to_vec());
+        let mut pdheader = PalmDocHeader::parse(&mut reader).unwrap();
+        assert!(pdheader.looks_valid());
+
+        pdheader.record_size = 1024;
+        assert!(pdheader.looks_valid());
+
+        for record_size in [0, 3000, 8192, u16::MAX] {
+            pdheader.record_size = record_size;
+            assert!(!pdheader.looks_valid());
+        }
+    }
+
+    #[test]
+    fn unknown_compression() {
+        let mut bytes = book::PALMDOCHEADER.to_vec();
+        bytes[0..2].copy_from_slice(&0x1234u16.to_be_bytes());
+        let pdheader = PalmDocHeader::parse(&mut book::u8_reader(bytes.clone())).unwrap();
+        assert_eq!(pdheader.compression(), Compression::Unknown(0x1234));
+        assert!(!pdheader.looks_valid());
+
+        let mut written = vec![];
+        pdheader.write(&mut Writer::new(&mut written)).unwrap();
+        assert_eq!(written, bytes);
+    }
+
     #[test]
     fn test_write() {
         let input_bytes = book::PALMDOCHEADER.to_vec();
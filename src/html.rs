@@ -0,0 +1,121 @@
+/// Removes markup tags from passed html replacing every tag with a space, so that
+/// words separated only by tags stay separated.
+pub(crate) fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => {
+                in_tag = false;
+                text.push(' ');
+            }
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+/// Splits passed html into pages before every `<mbp:pagebreak` and `<p class="pagebreak"`
+/// tag, matched case insensitively. Markers stay at the start of the page they open, so
+/// concatenating the pages yields the passed html.
+pub(crate) fn split_pages(html: &str) -> Vec<String> {
+    let lowercase = html.to_ascii_lowercase();
+    let mut breaks: Vec<usize> = ["<mbp:pagebreak", "<p class=\"pagebreak\""]
+        .iter()
+        .flat_map(|marker| lowercase.match_indices(marker).map(|(i, _)| i))
+        .filter(|&i| i > 0)
+        .collect();
+    breaks.sort_unstable();
+
+    let mut pages = vec![];
+    let mut start = 0;
+    for end in breaks.into_iter().chain(std::iter::once(html.len())) {
+        pages.push(html[start..end].to_string());
+        start = end;
+    }
+    pages
+}
+
+/// Returns the inside of every opening tag with passed lowercase name, matched case
+/// insensitively. For `<a href="x">` and name `a` this is ` href="x"`.
+pub(crate) fn find_tags<'a>(html: &'a str, name: &str) -> Vec<&'a str> {
+    let lowercase = html.to_ascii_lowercase();
+    let open = format!("<{}", name);
+    lowercase
+        .match_indices(&open)
+        .filter_map(|(start, _)| {
+            let attrs_start = start + open.len();
+            let rest = &lowercase[attrs_start..];
+            if !rest.starts_with(|c: char| c.is_ascii_whitespace() || c == '/' || c == '>') {
+                return None;
+            }
+            let end = attrs_start + rest.find('>')?;
+            Some(&html[attrs_start..end])
+        })
+        .collect()
+}
+
+/// Returns the value of attribute with passed lowercase name from the inside of a tag.
+/// Values may be double quoted, single quoted or unquoted.
+pub(crate) fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let lowercase = tag.to_ascii_lowercase();
+    let (start, _) = lowercase.match_indices(name).find(|&(i, _)| {
+        lowercase[..i].ends_with(|c: char| c.is_ascii_whitespace())
+            && lowercase[i + name.len()..].trim_start().starts_with('=')
+    })?;
+    let value = tag[start + name.len()..].trim_start()[1..].trim_start();
+    match value.chars().next()? {
+        quote @ ('"' | '\'') => value[1..].split(quote).next(),
+        _ => value
+            .split(|c: char| c.is_ascii_whitespace() || c == '/')
+            .next(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn strip_markup() {
+        assert_eq!(
+            strip_tags("<p>Hello<br/>world</p> 2 > 1"),
+            " Hello world  2 > 1"
+        );
+        assert_eq!(strip_tags("<unclosed tag"), "");
+    }
+
+    #[test]
+    fn tags_and_attributes() {
+        let tags = find_tags(
+            "<Reference type=\"toc\" TITLE='Table of Contents' filepos=0001234 /><references><ref>",
+            "reference",
+        );
+        assert_eq!(tags.len(), 1);
+        assert_eq!(attribute(tags[0], "type"), Some("toc"));
+        assert_eq!(attribute(tags[0], "title"), Some("Table of Contents"));
+        assert_eq!(attribute(tags[0], "filepos"), Some("0001234"));
+        assert_eq!(attribute(tags[0], "href"), None);
+        assert_eq!(attribute(" xtype=\"a\" type = b", "type"), Some("b"));
+    }
+
+    #[test]
+    fn split_into_pages() {
+        assert_eq!(
+            split_pages("<p>One</p><mbp:pagebreak/><p>Two</p><P CLASS=\"pagebreak\">Three</p>"),
+            vec![
+                "<p>One</p>",
+                "<mbp:pagebreak/><p>Two</p>",
+                "<P CLASS=\"pagebreak\">Three</p>"
+            ]
+        );
+        assert_eq!(
+            split_pages("<mbp:pagebreak />text"),
+            vec!["<mbp:pagebreak />text"]
+        );
+        assert_eq!(split_pages(""), vec![""]);
+    }
+}
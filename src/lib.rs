@@ -49,13 +49,17 @@ pub mod record;
 pub use crate::headers::MobiMetadata;
 pub(crate) mod book;
 pub(crate) mod compression;
+pub(crate) mod html;
 pub(crate) mod reader;
 pub(crate) mod writer;
 
 use compression::huff;
-use headers::{Compression, Encryption, Language, MobiType, TextEncoding};
+use headers::{
+    CdeType, Compression, CreatorSoftware, DrmInfo, Encryption, ExthRecord, Language, MobiFormat,
+    MobiHeader, MobiType, ParseWarning, TextEncoding, Timestamps,
+};
 pub(crate) use reader::Reader;
-use record::{RawRecord, RawRecords};
+use record::{PdbRecord, PdbRecords, RawRecord, RawRecords, RecordKind};
 pub(crate) use writer::Writer;
 
 #[cfg(feature = "time")]
@@ -65,6 +69,9 @@ use thiserror::Error;
 
 pub type MobiResult<T> = std::result::Result<T, MobiError>;
 
+/// Number of leading bytes of an obfuscated font that are XOR-ed with the key
+const OBFUSCATED_FONT_LEN: usize = 1040;
+
 #[derive(Debug, Error)]
 pub enum MobiError {
     #[error(transparent)]
@@ -75,6 +82,91 @@ pub enum MobiError {
     DecodeError(#[from] record::DecodeError),
     #[error(transparent)]
     HuffmanError(#[from] huff::HuffmanError),
+    /// Content is protected with Mobipocket DRM. Removing DRM is out of scope
+    /// of this library so the content of such books can't be extracted.
+    #[error("book content is encrypted")]
+    Encrypted,
+    /// Decoded markup contains a control character other than whitespace
+    #[error("content contains a control character at byte offset {0}")]
+    ControlCharacter(usize),
+    /// Book has more records than allowed by `ParseLimits`
+    #[error("book has {0} records which exceeds the limit")]
+    TooManyRecords(usize),
+    /// Record is larger than allowed by `ParseLimits`
+    #[error("record {0} is {1} bytes long which exceeds the limit")]
+    RecordTooLarge(usize, usize),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Limits of a book accepted by `Mobi::from_reader_with_limits`. Default limits
+/// accept any book.
+pub struct ParseLimits {
+    /// Maximum number of PDB records
+    pub max_records: usize,
+    /// Maximum size of a single record in bytes, as declared by record offsets
+    pub max_record_size: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_records: usize::MAX,
+            max_record_size: usize::MAX,
+        }
+    }
+}
+
+impl ParseLimits {
+    /// Checks record count and sizes of records. Size of the last record is only
+    /// known once the content is read, before that `content_len` should be 0.
+    fn check(&self, records: &PdbRecords, content_len: usize) -> MobiResult<()> {
+        let records = &records.records;
+        if records.len() > self.max_records {
+            return Err(MobiError::TooManyRecords(records.len()));
+        }
+
+        let ends = records
+            .iter()
+            .skip(1)
+            .map(|record| record.offset as usize)
+            .chain(std::iter::once(content_len));
+        for (i, (record, end)) in records.iter().zip(ends).enumerate() {
+            let size = end.saturating_sub(record.offset as usize);
+            if size > self.max_record_size {
+                return Err(MobiError::RecordTooLarge(i, size));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the number of HUFF/CDIC records declared in the MOBI header, all of them
+    /// are loaded at once to decompress the content.
+    fn check_huff(&self, mobi: &MobiHeader) -> MobiResult<()> {
+        let count = mobi.huff_record_count as usize;
+        if mobi.first_huff_record != u32::MAX && count > self.max_records {
+            return Err(MobiError::TooManyRecords(count));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+/// Location of a text found in the content
+pub struct TextMatch {
+    /// Index of the record containing the text
+    pub record_index: usize,
+    /// Offset in bytes of the text within the decoded record
+    pub byte_offset: usize,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+/// Reference from the `<guide>` section of the content
+pub struct GuideReference {
+    /// Type of the reference like `toc` or `start`
+    pub ref_type: String,
+    pub title: Option<String>,
+    /// Byte offset into the concatenated decompressed text, see `Mobi::resolve_filepos`
+    pub filepos: usize,
 }
 
 #[derive(Debug, Default)]
@@ -90,40 +182,279 @@ impl Mobi {
         Mobi::from_reader(&mut Reader::new(std::io::Cursor::new(bytes.as_ref())))
     }
 
+    /// Construct a Mobi object from an owned vector of bytes. Unlike `new` this
+    /// reuses the passed buffer as content instead of copying it.
+    pub fn from_bytes(mut bytes: Vec<u8>) -> MobiResult<Mobi> {
+        let mut reader = Reader::new(std::io::Cursor::new(bytes.as_slice()));
+        let metadata = MobiMetadata::from_reader(&mut reader)?;
+        // Zero-fill the headers like `read_to_end` does in `from_reader`.
+        let position = reader.position();
+        bytes[..position].fill(0);
+
+        Ok(Mobi {
+            content: bytes,
+            metadata,
+        })
+    }
+
     /// Construct a Mobi object from passed file path
     pub fn from_path<P: AsRef<Path>>(file_path: P) -> MobiResult<Mobi> {
         let mut reader = Reader::new(BufReader::new(File::open(file_path)?));
         Mobi::from_reader(&mut reader)
     }
 
+    /// Construct a Mobi object from passed file path recovering as much as possible
+    /// from a damaged file. Errors in EXTH header, book name and content are returned
+    /// as warnings while the affected data is left empty or truncated. Returns an error if
+    /// the file can't be opened or one of the PDB, PalmDOC or MOBI headers can't be parsed.
+    pub fn open_lenient<P: AsRef<Path>>(file_path: P) -> MobiResult<(Mobi, Vec<ParseWarning>)> {
+        let mut warnings = vec![];
+        let mut reader = Reader::new(BufReader::new(File::open(file_path)?));
+        let metadata = MobiMetadata::from_reader_recovering(&mut reader, &mut warnings)?;
+
+        let mut content = vec![0; reader.position()];
+        if let Err(e) = reader.read_to_end(&mut content) {
+            warnings.push(ParseWarning::Content(e));
+        }
+        Ok((Mobi { content, metadata }, warnings))
+    }
+
     /// Construct a Mobi object from an object that implements a Read trait
     pub fn from_read<R: io::Read>(reader: R) -> MobiResult<Mobi> {
         Mobi::from_reader(&mut Reader::new(reader))
     }
 
+    #[cfg(feature = "tokio")]
+    /// Construct a Mobi object from an object that implements tokio's AsyncRead trait.
+    /// The whole input is read asynchronously into memory before parsing.
+    ///
+    /// This function is only available using `tokio` feature
+    pub async fn from_async_read<R: tokio::io::AsyncRead + Unpin>(
+        mut reader: R,
+    ) -> MobiResult<Mobi> {
+        use tokio::io::AsyncReadExt;
+
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+        Mobi::from_bytes(bytes)
+    }
+
+    /// Construct a Mobi object from an object that implements a Read trait, failing
+    /// with an error if the book exceeds passed limits. The limits are checked before
+    /// the content is read wherever possible and no more content is read than the
+    /// records can hold.
+    pub fn from_reader_with_limits<R: io::Read>(
+        reader: R,
+        limits: ParseLimits,
+    ) -> MobiResult<Mobi> {
+        let mut reader = Reader::new(reader);
+        let metadata = MobiMetadata::from_reader(&mut reader)?;
+        limits.check(&metadata.records, 0)?;
+        limits.check_huff(&metadata.mobi)?;
+
+        // One byte past the largest allowed last record is enough to detect it's too large
+        let max_len = metadata
+            .records
+            .records
+            .last()
+            .map_or(0, |record| record.offset as usize)
+            .saturating_add(limits.max_record_size)
+            .saturating_add(1);
+        let limit = max_len.saturating_sub(reader.position());
+        let mobi = Mobi::with_content(&mut reader, metadata, limit as u64)?;
+        limits.check(&mobi.metadata.records, mobi.content.len())?;
+        Ok(mobi)
+    }
+
     fn from_reader<R: io::Read>(reader: &mut Reader<R>) -> MobiResult<Mobi> {
         let metadata = MobiMetadata::from_reader(reader)?;
+        Mobi::with_content(reader, metadata, u64::MAX)
+    }
+
+    /// Reads at most `limit` bytes of content following the metadata.
+    fn with_content<R: io::Read>(
+        reader: &mut Reader<R>,
+        metadata: MobiMetadata,
+        limit: u64,
+    ) -> MobiResult<Mobi> {
         Ok(Mobi {
             content: {
                 let mut buf = vec![0; reader.position()];
-                reader.read_to_end(&mut buf)?;
+                reader.read_to_end_limited(&mut buf, limit)?;
                 buf
             },
             metadata,
         })
     }
 
-    #[allow(dead_code)]
-    fn write(&self, writer: &mut impl io::Write) -> io::Result<()> {
+    /// Writes this book to a writer. The headers record is rebuilt from `metadata` and
+    /// all records are laid out one after another, so the record list is recomputed.
+    pub fn write_to<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        let records = self.record_slices();
+        let mut headers_record = vec![];
+        self.metadata
+            .write_headers_record(&mut Writer::new(&mut headers_record))?;
+        // Keep whatever followed the name in the original headers record
+        if let Some(original) = records.first() {
+            let name_end = self.metadata.mobi.name_offset as usize + self.metadata.name.len();
+            headers_record.extend_from_slice(original.get(name_end..).unwrap_or_default());
+        }
+
+        let sizes =
+            std::iter::once(headers_record.len()).chain(records.iter().skip(1).map(|r| r.len()));
+        let pdb_records = self.metadata.records.laid_out(sizes);
+
         let mut w = Writer::new(writer);
+        self.metadata
+            .header
+            .write(&mut w, pdb_records.num_records())?;
+        pdb_records.write(&mut w)?;
+        w.write_be(headers_record.as_slice())?;
+        for record in records.iter().skip(1) {
+            w.write_be(*record)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the cover image with passed image data. If the book has no cover, the
+    /// image is inserted as a new record after the last content record and a cover offset
+    /// EXTH record pointing to it is added. Changes are kept in memory, use `write_to` to save them.
+    pub fn replace_cover(&mut self, image: &[u8]) {
+        let mut records: Vec<Vec<u8>> = self.record_slices().iter().map(|r| r.to_vec()).collect();
+        match self.metadata.cover_record_index() {
+            Some(index) if index < records.len() => records[index] = image.to_vec(),
+            _ => {
+                // New image becomes the last content record, before FLIS, FCIS and EOF
+                let mobi = &mut self.metadata.mobi;
+                let index = (usize::from(mobi.last_content_record) + 1).min(records.len());
+                records.insert(index, image.to_vec());
+                // Readable records end at the image, it must not become one of them
+                let first_non_book_index = mobi.first_non_book_index;
+                mobi.shift_record_indices(index as u32, 1);
+                if first_non_book_index as usize == index {
+                    mobi.first_non_book_index = first_non_book_index;
+                }
+                mobi.last_content_record = index as u16;
+                if mobi.first_image_index as usize > index {
+                    mobi.first_image_index = index as u32;
+                }
+                let offset = index - mobi.first_image_index as usize;
+                self.metadata.set_cover_offset(offset as u32);
+            }
+        }
+        self.set_records(&records);
+    }
 
-        self.metadata.write_into(&mut w)?;
+    /// Re-encodes text of a CP1252 book as UTF-8. Text records are replaced with records
+    /// holding the UTF-8 text split on character boundaries, PalmDOC compressed unless
+    /// the book was uncompressed. Huffman compressed books are recompressed with PalmDOC
+    /// and no longer reference their HUFF/CDIC records.
+    /// Does nothing if the book isn't CP1252 encoded.
+    pub fn reencode_to_utf8(&mut self) -> MobiResult<()> {
+        if self.text_encoding() != TextEncoding::CP1252 {
+            return Ok(());
+        }
+        let text = self.content_as_string()?;
+        let compress = matches!(self.compression(), Compression::PalmDoc | Compression::Huff);
+        let was_huff = self.compression() == Compression::Huff;
+        let record_size = match self.metadata.palmdoc.record_size {
+            0 => 4096,
+            size => usize::from(size),
+        };
 
-        let first_offset = self.metadata.records.records[1].offset as usize;
-        let fill = first_offset - w.bytes_written();
-        w.write_be(vec![0; fill])?;
-        // TODO: Consider record compression and everything else.
-        w.write_be(&self.content[first_offset..])
+        let mut text_records = vec![];
+        let mut rest = text.as_str();
+        while !rest.is_empty() {
+            let mut end = record_size.min(rest.len());
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            let (chunk, tail) = rest.split_at(end);
+            text_records.push(if compress {
+                compression::palmdoc::compress(chunk.as_bytes())
+            } else {
+                chunk.as_bytes().to_vec()
+            });
+            rest = tail;
+        }
+
+        let mut records: Vec<Vec<u8>> = self.record_slices().iter().map(|r| r.to_vec()).collect();
+        let range = self.readable_records_range();
+        let start = range.start.min(records.len());
+        let range = start..range.end.clamp(start, records.len());
+        let delta = text_records.len() as i64 - range.len() as i64;
+        records.splice(range.clone(), text_records.iter().cloned());
+        self.set_records(&records);
+
+        let mobi = &mut self.metadata.mobi;
+        let last_content_record = usize::from(mobi.last_content_record);
+        mobi.shift_record_indices(range.end as u32, delta);
+        // Books without images end their content with the last text record
+        if mobi.last_content_record != u16::MAX && range.contains(&last_content_record) {
+            mobi.last_content_record = (range.start + text_records.len()).saturating_sub(1) as u16;
+        }
+        if was_huff {
+            mobi.first_huff_record = u32::MAX;
+            mobi.huff_record_count = 0;
+        }
+        mobi.text_encoding = TextEncoding::UTF8;
+        // Trailing entries are not written to the new records
+        mobi.extra_record_data_flags = 0;
+        let palmdoc = &mut self.metadata.palmdoc;
+        palmdoc.compression = if compress {
+            Compression::PalmDoc
+        } else {
+            Compression::No
+        };
+        palmdoc.text_length = text.len() as u32;
+        palmdoc.record_count = text_records.len() as u16;
+        Ok(())
+    }
+
+    /// Returns the data of every record, untrimmed and clamped to the content length.
+    fn record_slices(&self) -> Vec<&[u8]> {
+        let records = &self.metadata.records.records;
+        let len = self.content.len();
+        records
+            .iter()
+            .enumerate()
+            .map(|(i, record)| {
+                let start = (record.offset as usize).min(len);
+                let end = records
+                    .get(i + 1)
+                    .map_or(len, |next| (next.offset as usize).min(len));
+                &self.content[start..end.max(start)]
+            })
+            .collect()
+    }
+
+    /// Replaces all records, laying them out one after another. Records past the
+    /// original count get new unique ids.
+    fn set_records(&mut self, records: &[Vec<u8>]) {
+        let old = &self.metadata.records.records;
+        let start = old
+            .first()
+            .map_or(0, |r| r.offset as usize)
+            .min(self.content.len());
+        let next_id = old.iter().map(|r| r.id + 1).max().unwrap_or(0);
+        let ids: Vec<u32> = old
+            .iter()
+            .map(|r| r.id)
+            .chain(next_id..)
+            .take(records.len())
+            .collect();
+
+        self.content.truncate(start);
+        let mut new_records = Vec::with_capacity(records.len());
+        for (data, id) in records.iter().zip(ids) {
+            new_records.push(PdbRecord {
+                id,
+                offset: self.content.len() as u32,
+            });
+            self.content.extend_from_slice(data);
+        }
+        self.metadata.records.records = new_records;
+        self.metadata.header.num_records = records.len() as u16;
     }
 
     /// Returns an author of this book
@@ -131,11 +462,21 @@ impl Mobi {
         self.metadata.author()
     }
 
+    /// Returns all authors of this book
+    pub fn authors(&self) -> Vec<String> {
+        self.metadata.authors()
+    }
+
     /// Returns this books publisher
     pub fn publisher(&self) -> Option<String> {
         self.metadata.publisher()
     }
 
+    /// Returns all publishers of this book
+    pub fn publishers(&self) -> Vec<String> {
+        self.metadata.publishers()
+    }
+
     /// Returns description record if such exists
     pub fn description(&self) -> Option<String> {
         self.metadata.description()
@@ -151,16 +492,70 @@ impl Mobi {
         self.metadata.publish_date()
     }
 
+    /// Returns the series name and index if the book is a part of a series
+    pub fn series(&self) -> Option<(String, Option<f32>)> {
+        self.metadata.series()
+    }
+
+    #[cfg(feature = "time")]
+    /// Returns publish_date record parsed as a datetime if it's in a known format
+    /// This field is only available using `time` feature
+    pub fn published_datetime(&self) -> Option<NaiveDateTime> {
+        self.metadata.published_datetime()
+    }
+
+    /// Returns last_update_time record if such exists
+    pub fn last_update_time(&self) -> Option<String> {
+        self.metadata.last_update_time()
+    }
+
+    #[cfg(feature = "time")]
+    /// Returns last_update_time record parsed as a datetime if it's in a known format
+    /// This field is only available using `time` feature
+    pub fn last_update_datetime(&self) -> Option<NaiveDateTime> {
+        self.metadata.last_update_datetime()
+    }
+
+    /// Returns the type of this document read from cdetype record if such exists
+    pub fn cde_type(&self) -> Option<CdeType> {
+        self.metadata.cde_type()
+    }
+
+    /// Returns information about the software that created this book if such exists
+    pub fn creator_software(&self) -> Option<CreatorSoftware> {
+        self.metadata.creator_software()
+    }
+
     /// Returns contributor record if such exists
     pub fn contributor(&self) -> Option<String> {
         self.metadata.contributor()
     }
 
+    /// Returns dictionary short name record if such exists
+    pub fn dictionary_short_name(&self) -> Option<String> {
+        self.metadata.dictionary_short_name()
+    }
+
+    /// Returns retail price record along with the retail price currency record if such exist
+    pub fn retail_price(&self) -> Option<(String, Option<String>)> {
+        self.metadata.retail_price()
+    }
+
     /// Returns title record if such exists
     pub fn title(&self) -> String {
         self.metadata.title()
     }
 
+    /// Returns title record if such exists without falling back to the book name
+    pub fn title_opt(&self) -> Option<String> {
+        self.metadata.title_opt()
+    }
+
+    /// Returns the database name from PDB header
+    pub fn pdb_name(&self) -> String {
+        self.metadata.pdb_name()
+    }
+
     /// Returns text encoding used in ebook
     pub fn text_encoding(&self) -> TextEncoding {
         self.metadata.text_encoding()
@@ -171,11 +566,36 @@ impl Mobi {
         self.metadata.mobi_type()
     }
 
+    /// Checks if this ebook is a periodical
+    pub fn is_periodical(&self) -> bool {
+        self.metadata.is_periodical()
+    }
+
+    /// Returns the version of the MOBI format used by this ebook
+    pub fn format(&self) -> MobiFormat {
+        self.metadata.format()
+    }
+
     /// Returns language of the ebook
     pub fn language(&self) -> Language {
         self.metadata.language()
     }
 
+    /// Returns input and output languages of a dictionary
+    pub fn dictionary_languages(&self) -> Option<(Language, Language)> {
+        self.metadata.dictionary_languages()
+    }
+
+    /// Returns unique id of this ebook
+    pub fn book_id(&self) -> u32 {
+        self.metadata.book_id()
+    }
+
+    /// Returns version of the generator that created this ebook
+    pub fn generator_version(&self) -> u32 {
+        self.metadata.generator_version()
+    }
+
     #[cfg(feature = "time")]
     /// Returns creation datetime
     /// This field is only available using `time` feature
@@ -202,6 +622,12 @@ impl Mobi {
         self.metadata.mod_time()
     }
 
+    /// Returns raw creation and last modification timestamps regardless of
+    /// the `time` feature
+    pub fn timestamps(&self) -> Timestamps {
+        self.metadata.timestamps()
+    }
+
     /// Returns compression method used on this file
     pub fn compression(&self) -> Compression {
         self.metadata.compression()
@@ -211,6 +637,22 @@ impl Mobi {
         self.metadata.encryption()
     }
 
+    /// Checks if there is DRM on this book
+    pub fn has_drm(&self) -> bool {
+        self.metadata.has_drm()
+    }
+
+    /// Returns DRM descriptor if there is DRM on this book
+    pub fn drm_info(&self) -> Option<DrmInfo> {
+        self.metadata.drm_info()
+    }
+
+    /// Returns the size of the whole book text in bytes before decompression and
+    /// decoding, useful as a total for progress indicators.
+    pub fn uncompressed_text_length(&self) -> u32 {
+        self.metadata.uncompressed_text_length()
+    }
+
     /// Returns the readable reacord range - from first content record to first
     /// non book index.
     pub fn readable_records_range(&self) -> Range<usize> {
@@ -219,71 +661,299 @@ impl Mobi {
     }
 
     /// Returns raw records that contain compressed, encrypted and encoded content slices.
-    pub fn raw_records(&self) -> RawRecords {
+    pub fn raw_records(&self) -> RawRecords<'_> {
         self.metadata.records.parse(&self.content)
     }
 
-    /// Returns all records classified as image records.
-    pub fn image_records(&self) -> Vec<RawRecord> {
+    /// Returns all records classified as image records. Only records from first image
+    /// index up to last content record are considered, excluding FLIS and FCIS records.
+    pub fn image_records(&self) -> Vec<RawRecord<'_>> {
+        self.indexed_image_records()
+            .into_iter()
+            .map(|(_, record)| record)
+            .collect()
+    }
+
+    fn indexed_image_records(&self) -> Vec<(usize, RawRecord<'_>)> {
+        let mobi = &self.metadata.mobi;
+        let first_image_index = mobi.first_image_index as usize;
+        let last_content_record = mobi.last_content_record as usize;
+        let image_range = if last_content_record >= first_image_index {
+            first_image_index..last_content_record + 1
+        } else {
+            first_image_index..usize::MAX
+        };
+        let structural = [mobi.flis_record as usize, mobi.fcis_record as usize];
+
+        image_range
+            .clone()
+            .zip(self.raw_records().range(image_range))
+            .filter(|(i, record)| !structural.contains(i) && record.is_image_record())
+            .map(|(i, record)| (i, *record))
+            .collect()
+    }
+
+    /// Removes all image records along with cover and thumbnail offset records. Records
+    /// following the images are moved down and first image index is cleared. Changes are
+    /// kept in memory, use `write_to` to save them.
+    pub fn strip_images(&mut self) {
+        let images: Vec<usize> = self
+            .indexed_image_records()
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        let mut records: Vec<Vec<u8>> = self.record_slices().iter().map(|r| r.to_vec()).collect();
+
+        let mobi = &mut self.metadata.mobi;
+        for &i in images.iter().rev() {
+            records.remove(i);
+            if usize::from(mobi.last_content_record) == i {
+                mobi.last_content_record = mobi.last_content_record.saturating_sub(1);
+            }
+            mobi.shift_record_indices(i as u32 + 1, -1);
+        }
+        mobi.first_image_index = u32::MAX;
+        for record in [ExthRecord::CoverOffset, ExthRecord::ThumbOffset] {
+            self.metadata.exth.records.shift_remove(&record);
+        }
+        self.set_records(&records);
+    }
+
+    /// Returns content of all records holding embedded fonts. Only records from first
+    /// image index onwards are considered.
+    pub fn embedded_fonts(&self) -> Vec<&[u8]> {
         self.raw_records()
             .range(self.metadata.mobi.first_image_index as usize..)
             .iter()
-            .copied()
-            .filter(|record| record.is_image_record())
+            .filter(|record| record.is_font_record())
+            .map(|record| record.content)
             .collect()
     }
 
-    fn palmdoc_string_lossy(&self) -> String {
+    /// Deobfuscates a font by XOR-ing its leading bytes with the font signature record
+    /// used as the key. Returns `None` if the book has no font signature.
+    pub fn deobfuscate_font(&self, font: &[u8]) -> Option<Vec<u8>> {
+        let key = self
+            .metadata
+            .exth
+            .get_record(ExthRecord::FontSignature)?
+            .first()
+            .filter(|key| !key.is_empty())?;
+        let mut font = font.to_vec();
+        for (i, byte) in font.iter_mut().take(OBFUSCATED_FONT_LEN).enumerate() {
+            *byte ^= key[i % key.len()];
+        }
+        Some(font)
+    }
+
+    /// Returns content of a resource referenced as `kindle:embed:XXXX` in the markup.
+    /// Resource indices start at 1 and are relative to the first image record.
+    pub fn image_by_resource_index(&self, index: usize) -> Option<&[u8]> {
+        let record =
+            (self.metadata.mobi.first_image_index as usize).checked_add(index.checked_sub(1)?)?;
+        self.raw_records()
+            .range(record..=record)
+            .iter()
+            .next()
+            .map(|record| record.content)
+    }
+
+    /// Returns the size in bytes of all image records as stored in the file
+    pub fn total_image_bytes(&self) -> usize {
+        self.image_records().iter().map(RawRecord::len).sum()
+    }
+
+    /// Returns the size in bytes of all readable records as stored in the file,
+    /// before decompression
+    pub fn total_text_bytes(&self) -> usize {
+        self.readable_records().map(|record| record.len()).sum()
+    }
+
+    /// Checks if this book has no readable content, either because there are no readable
+    /// records or all of them are empty. Unlike checking `content_as_string` this doesn't
+    /// decode anything.
+    pub fn is_empty_content(&self) -> bool {
+        self.readable_records().all(|record| record.is_empty())
+    }
+
+    /// Returns the source archive embedded in a SRCS record, usually a zip of the files
+    /// the book was built from, if such record exists.
+    pub fn source_archive(&self) -> Option<&[u8]> {
+        self.raw_records()
+            .into_iter()
+            .find(|record| record.content.starts_with(b"SRCS"))
+            // Payload follows a 16 byte SRCS header
+            .and_then(|record| record.content.get(16..))
+    }
+
+    /// Returns a CRC-32 checksum of content of all records. Comparing it with a stored
+    /// checksum detects corrupted or partially downloaded books without decoding them.
+    pub fn records_checksum(&self) -> u32 {
+        self.raw_records()
+            .into_iter()
+            .fold(0, |crc, record| record::crc32(crc, record.content))
+    }
+
+    /// Returns all records paired with their kind. Kind of a record is decided from
+    /// the record index fields of the Mobi header and magic bytes of the record.
+    pub fn classified_records(&self) -> impl Iterator<Item = (RecordKind, RawRecord<'_>)> {
+        let text_range = self.readable_records_range();
+        let first_image_index = self.metadata.mobi.first_image_index as usize;
+
+        self.raw_records()
+            .into_iter()
+            .enumerate()
+            .map(move |(i, record)| {
+                let kind = match record.content.get(..4) {
+                    _ if text_range.contains(&i) => RecordKind::Text,
+                    _ if record.is_huff_record() => RecordKind::Huff,
+                    _ if record.is_cdic_record() => RecordKind::Cdic,
+                    Some(b"FLIS") => RecordKind::Flis,
+                    Some(b"FCIS") => RecordKind::Fcis,
+                    Some(b"INDX") => RecordKind::Index,
+                    _ if i >= first_image_index && record.is_image_record() => RecordKind::Image,
+                    _ => RecordKind::Other,
+                };
+                (kind, record)
+            })
+    }
+
+    /// Returns an iterator over decompressed bytes of every readable record. Bytes
+    /// are not decoded, so that callers can handle the text encoding themselves.
+    /// HUFF/CDIC compressed records are all decompressed before the first one is yielded.
+    pub fn content_byte_chunks(&self) -> impl Iterator<Item = MobiResult<Vec<u8>>> + '_ {
+        let chunks: Box<dyn Iterator<Item = MobiResult<Vec<u8>>>> =
+            match self.check_encryption().map(|_| self.compression()) {
+                Err(e) => Box::new(std::iter::once(Err(e))),
+                Ok(Compression::No | Compression::Unknown(_)) => {
+                    Box::new(self.readable_records().map(|r| Ok(r.content.to_vec())))
+                }
+                Ok(Compression::PalmDoc) => Box::new(
+                    self.readable_records()
+                        .map(|r| Ok(r.decompress_palmdoc().0)),
+                ),
+                Ok(Compression::Huff) => match self.huff_data(&self.raw_records()) {
+                    Ok(data) => Box::new(data.into_iter().map(Ok)),
+                    Err(e) => Box::new(std::iter::once(Err(e))),
+                },
+            };
+        chunks
+    }
+
+    /// Finds all occurrences of passed text in readable records. Every record is decompressed
+    /// and lossily decoded separately, so text spanning two records isn't found.
+    pub fn find_text(&self, needle: &str) -> MobiResult<Vec<TextMatch>> {
         let encoding = self.text_encoding();
+        let first_record = self.readable_records_range().start;
+        let mut matches = vec![];
+
+        for (i, chunk) in self.content_byte_chunks().enumerate() {
+            let text = record::content_to_string_lossy(&chunk?, encoding);
+            matches.extend(
+                text.match_indices(needle)
+                    .map(|(byte_offset, _)| TextMatch {
+                        record_index: first_record + i,
+                        byte_offset,
+                    }),
+            );
+        }
+        Ok(matches)
+    }
+
+    /// Returns offsets in bytes into the concatenated decompressed text at which each
+    /// readable record starts.
+    pub fn record_text_offsets(&self) -> MobiResult<Vec<usize>> {
+        let mut offsets = vec![];
+        let mut offset = 0;
+        for chunk in self.content_byte_chunks() {
+            offsets.push(offset);
+            offset += chunk?.len();
+        }
+        Ok(offsets)
+    }
+
+    /// Maps a `filepos` byte offset into the concatenated decompressed text to the index
+    /// of the record containing it and the offset within that decompressed record.
+    /// Returns `None` if the offset points past the text or a record can't be decompressed.
+    pub fn resolve_filepos(&self, filepos: usize) -> Option<(usize, usize)> {
+        let first_record = self.readable_records_range().start;
+        let mut record_start = 0;
+
+        for (i, chunk) in self.content_byte_chunks().enumerate() {
+            let record_end = record_start + chunk.ok()?.len();
+            if filepos < record_end {
+                return Some((first_record + i, filepos - record_start));
+            }
+            record_start = record_end;
+        }
+        None
+    }
+
+    fn readable_records(&self) -> impl Iterator<Item = RawRecord<'_>> {
+        let range = self.readable_records_range();
         self.raw_records()
+            .into_iter()
+            .skip(range.start)
+            .take(range.end.saturating_sub(range.start))
+    }
+
+    fn palmdoc_string_lossy(&self, records: &RawRecords) -> String {
+        let encoding = self.text_encoding();
+        records
             .range(self.readable_records_range())
             .iter()
             .map(|record| record.decompress_palmdoc().to_string_lossy(encoding))
             .collect()
     }
 
-    fn palmdoc_string(&self) -> MobiResult<String> {
+    fn palmdoc_string(&self, records: &RawRecords) -> MobiResult<String> {
         let encoding = self.text_encoding();
         let mut s = String::new();
 
-        for record in self.raw_records().range(self.readable_records_range()) {
+        for record in records.range(self.readable_records_range()) {
             let content = record.decompress_palmdoc().to_string(encoding)?;
             s.push_str(&content);
         }
         Ok(s)
     }
 
-    fn no_compression_string_lossy(&self) -> String {
+    fn no_compression_string_lossy(&self, records: &RawRecords) -> String {
         let encoding = self.text_encoding();
-        self.raw_records()
+        records
             .range(self.readable_records_range())
             .iter()
             .map(|r| record::content_to_string_lossy(r.content, encoding))
             .collect()
     }
 
-    fn no_compression_string(&self) -> MobiResult<String> {
+    fn no_compression_string(&self, records: &RawRecords) -> MobiResult<String> {
         let encoding = self.text_encoding();
         let mut s = String::new();
-        for record in self.raw_records().range(self.readable_records_range()) {
+        for record in records.range(self.readable_records_range()) {
             let content = record::content_to_string(record.content, encoding)?;
             s.push_str(&content);
         }
         Ok(s)
     }
 
-    fn huff_data(&self) -> MobiResult<Vec<Vec<u8>>> {
-        let records = self.raw_records();
+    fn huff_data(&self, records: &RawRecords) -> MobiResult<Vec<Vec<u8>>> {
+        self.huff_data_in(records, self.readable_records_range())
+    }
+
+    fn huff_data_in(&self, records: &RawRecords, range: Range<usize>) -> MobiResult<Vec<Vec<u8>>> {
         let huff_start = self.metadata.mobi.first_huff_record as usize;
         let huff_count = self.metadata.mobi.huff_record_count as usize;
-        let huffs: Vec<_> = records
-            .range(huff_start..huff_start + huff_count)
+        let huff_end = huff_start.saturating_add(huff_count);
+        if huff_count == 0 || huff_end > records.records().len() {
+            return Err(huff::HuffmanError::MissingHuffRecord.into());
+        }
+        let huffs: Vec<_> = records.records()[huff_start..huff_end]
             .iter()
             .map(|record| record.content)
             .collect();
 
         let sections: Vec<_> = records
-            .range(self.readable_records_range())
+            .range(range)
             .iter()
             .map(|record| record.content)
             .collect();
@@ -291,10 +961,10 @@ impl Mobi {
         Ok(huff::decompress(&huffs, &sections)?)
     }
 
-    fn huff_string_lossy(&self) -> MobiResult<String> {
+    fn huff_string_lossy(&self, records: &RawRecords) -> MobiResult<String> {
         let encoding = self.text_encoding();
         let mut s = String::new();
-        let data = self.huff_data()?;
+        let data = self.huff_data(records)?;
 
         for section in data {
             let content = record::content_to_string_lossy(&section, encoding);
@@ -303,10 +973,10 @@ impl Mobi {
         Ok(s)
     }
 
-    fn huff_string(&self) -> MobiResult<String> {
+    fn huff_string(&self, records: &RawRecords) -> MobiResult<String> {
         let encoding = self.text_encoding();
         let mut s = String::new();
-        let data = self.huff_data()?;
+        let data = self.huff_data(records)?;
 
         for section in data {
             let content = record::content_to_string(&section, encoding)?;
@@ -315,14 +985,35 @@ impl Mobi {
         Ok(s)
     }
 
+    fn check_encryption(&self) -> MobiResult<()> {
+        if self.encryption().is_encrypted() {
+            Err(MobiError::Encrypted)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Returns all readable records content decompressed as a String.
     /// There are only two supported encodings in mobi format (UTF8, WIN1252)
-    /// and both are losely converted by this function
+    /// and both are losely converted by this function. Returns an empty string
+    /// if the content is encrypted.
     pub fn content_as_string_lossy(&self) -> String {
+        self.content_as_string_checked_lossy().unwrap_or_default()
+    }
+
+    /// Returns all readable records content decompressed as a String.
+    /// Records are losely converted like in `content_as_string_lossy` but
+    /// errors encountered while decompressing HUFF/CDIC content are returned
+    /// instead of resulting in an empty string.
+    pub fn content_as_string_checked_lossy(&self) -> MobiResult<String> {
+        self.check_encryption()?;
+        let records = self.raw_records();
         match self.compression() {
-            Compression::No => self.no_compression_string_lossy(),
-            Compression::PalmDoc => self.palmdoc_string_lossy(),
-            Compression::Huff => self.huff_string_lossy().unwrap_or_default(),
+            Compression::No | Compression::Unknown(_) => {
+                Ok(self.no_compression_string_lossy(&records))
+            }
+            Compression::PalmDoc => Ok(self.palmdoc_string_lossy(&records)),
+            Compression::Huff => self.huff_string_lossy(&records),
         }
     }
 
@@ -330,17 +1021,914 @@ impl Mobi {
     /// This function is a strict version returning error on first encountered
     /// decoding error.
     pub fn content_as_string(&self) -> MobiResult<String> {
+        self.check_encryption()?;
+        let records = self.raw_records();
         match self.compression() {
-            Compression::No => self.no_compression_string(),
-            Compression::PalmDoc => self.palmdoc_string(),
-            Compression::Huff => self.huff_string(),
+            Compression::No | Compression::Unknown(_) => self.no_compression_string(&records),
+            Compression::PalmDoc => self.palmdoc_string(&records),
+            Compression::Huff => self.huff_string(&records),
+        }
+    }
+
+    /// Returns all readable records content decompressed as HTML markup. The markup
+    /// is the same as in `content_as_string` but it is additionally verified to not
+    /// contain control characters other than tabs and line breaks.
+    ///
+    /// Links in Mobi books point to `filepos` attributes which are byte offsets into
+    /// the concatenated decompressed text, not into the returned String.
+    pub fn content_as_html(&self) -> MobiResult<String> {
+        let content = self.content_as_string()?;
+        if let Some((offset, _)) = content
+            .char_indices()
+            .find(|(_, c)| c.is_control() && !matches!(c, '\t' | '\n' | '\r'))
+        {
+            return Err(MobiError::ControlCharacter(offset));
+        }
+        Ok(content)
+    }
+
+    /// Returns true if the content can be decoded strictly with `content_as_string`.
+    /// Encrypted content or content that fails to decompress isn't valid either.
+    pub fn content_encoding_is_valid(&self) -> bool {
+        self.content_as_string().is_ok()
+    }
+
+    /// Returns decompressed content of records in passed range of record indices. The range
+    /// is clamped to readable records so that it's safe to pass any range.
+    pub fn content_slice(&self, record_range: Range<usize>) -> MobiResult<Vec<u8>> {
+        self.check_encryption()?;
+        let readable = self.readable_records_range();
+        let range = record_range.start.max(readable.start)..record_range.end.min(readable.end);
+
+        let raw_records = self.raw_records();
+        let records = raw_records.range(range.clone());
+        Ok(match self.compression() {
+            Compression::No | Compression::Unknown(_) => {
+                records.iter().flat_map(|r| r.content).copied().collect()
+            }
+            Compression::PalmDoc => records
+                .iter()
+                .flat_map(|r| r.decompress_palmdoc().0)
+                .collect(),
+            Compression::Huff => self.huff_data_in(&raw_records, range)?.concat(),
+        })
+    }
+
+    /// Returns at most `max_chars` first characters of the content decoded like in
+    /// `content_as_string`. Records are decompressed only until enough characters are
+    /// decoded, except for HUFF/CDIC compressed books which are decompressed whole.
+    pub fn preview(&self, max_chars: usize) -> MobiResult<String> {
+        let encoding = self.text_encoding();
+        let mut preview = String::new();
+        let mut chars = 0;
+
+        for chunk in self.content_byte_chunks() {
+            if chars >= max_chars {
+                break;
+            }
+            let content = record::content_to_string(&chunk?, encoding)?;
+            chars += content.chars().count();
+            preview.push_str(&content);
+        }
+
+        if let Some((end, _)) = preview.char_indices().nth(max_chars) {
+            preview.truncate(end);
         }
+        Ok(preview)
+    }
+
+    /// Returns the content split into pages on `<mbp:pagebreak/>` markers and paragraphs
+    /// with the `pagebreak` class. Markers stay at the start of the page they open. Pages
+    /// don't align with record boundaries.
+    pub fn pages(&self) -> MobiResult<Vec<String>> {
+        Ok(html::split_pages(&self.content_as_string()?))
+    }
+
+    /// Returns references from the `<guide>` section of the content. References without
+    /// a type or a numeric `filepos` attribute are skipped.
+    pub fn guide_references(&self) -> MobiResult<Vec<GuideReference>> {
+        let content = self.content_as_string()?;
+        let lowercase = content.to_ascii_lowercase();
+        let guide = match lowercase.find("<guide") {
+            Some(start) => {
+                let end = lowercase[start..]
+                    .find("</guide>")
+                    .map_or(content.len(), |end| start + end);
+                &content[start..end]
+            }
+            None => return Ok(vec![]),
+        };
+
+        Ok(html::find_tags(guide, "reference")
+            .into_iter()
+            .filter_map(|tag| {
+                Some(GuideReference {
+                    ref_type: html::attribute(tag, "type")?.to_string(),
+                    title: html::attribute(tag, "title").map(str::to_string),
+                    filepos: html::attribute(tag, "filepos")?.parse().ok()?,
+                })
+            })
+            .collect())
+    }
+
+    /// Returns the number of whitespace separated words in the content with markup
+    /// removed. The content is decoded strictly like in `content_as_string`.
+    pub fn word_count(&self) -> MobiResult<usize> {
+        let content = self.content_as_string()?;
+        Ok(html::strip_tags(&content).split_whitespace().count())
+    }
+
+    /// Returns all readable records content decompressed as a String.
+    /// Records are losely converted like in `content_as_string_checked_lossy`.
+    /// This function never panics for a book that was successfully parsed, record
+    /// ranges pointing past the content are clamped and HUFF/CDIC errors are returned.
+    pub fn try_content(&self) -> MobiResult<String> {
+        self.content_as_string_checked_lossy()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::book;
+    use crate::headers::ExtHeader;
+
+    #[test]
+    fn test_content_as_string_checked_lossy_corrupt_huff() {
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"HUFF\0\0\0\x01", b"CDIC"]);
+        m.metadata.palmdoc.compression = Compression::Huff;
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_huff_record = 2;
+        m.metadata.mobi.huff_record_count = 2;
+
+        assert!(m.content_as_string_checked_lossy().is_err());
+        assert_eq!(m.content_as_string_lossy(), "");
+    }
+
+    #[test]
+    fn test_huff_records_out_of_bounds() {
+        let mut m = book::mobi_with_records(&[b"header", b"text"]);
+        m.metadata.palmdoc.compression = Compression::Huff;
+
+        for (first, count) in [(0, 0), (2, 1), (1, 5), (u32::MAX, u32::MAX)] {
+            m.metadata.mobi.first_huff_record = first;
+            m.metadata.mobi.huff_record_count = count;
+            assert!(matches!(
+                m.content_as_string(),
+                Err(MobiError::HuffmanError(
+                    huff::HuffmanError::MissingHuffRecord
+                ))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        let mobi = Mobi::new(book::full_book()).unwrap();
+        let owned = Mobi::from_bytes(book::full_book()).unwrap();
+
+        assert_eq!(
+            format!("{:?}", mobi.metadata),
+            format!("{:?}", owned.metadata)
+        );
+        assert_eq!(mobi.content, owned.content);
+        assert!(Mobi::from_bytes(book::HEADER.to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_content_parses_records_once() {
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"HUFF\0\0\0\x01", b"CDIC"]);
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_huff_record = 2;
+        m.metadata.mobi.huff_record_count = 2;
+
+        for compression in [Compression::No, Compression::PalmDoc, Compression::Huff] {
+            m.metadata.palmdoc.compression = compression;
+            record::PARSE_COUNT.with(|count| count.set(0));
+            let _ = m.content_as_string();
+            let _ = m.content_as_string_lossy();
+            let _ = m.content_slice(0..usize::MAX);
+            assert_eq!(record::PARSE_COUNT.with(|count| count.get()), 3);
+        }
+    }
+
+    #[test]
+    fn test_book_id_and_generator_version() {
+        let mobi = Mobi::new(book::full_book()).unwrap();
+        assert_eq!(mobi.book_id(), 3428045761);
+        assert_eq!(mobi.generator_version(), 6);
+    }
+
+    #[test]
+    fn test_drm() {
+        let mut mobi = Mobi::new(book::full_book()).unwrap();
+        assert!(!mobi.has_drm());
+        assert_eq!(mobi.drm_info(), None);
+
+        mobi.metadata.mobi.drm_offset = 360;
+        mobi.metadata.mobi.drm_count = 1;
+        mobi.metadata.mobi.drm_size = 48;
+        assert!(mobi.has_drm());
+        assert_eq!(
+            mobi.drm_info(),
+            Some(DrmInfo {
+                offset: 360,
+                count: 1,
+                size: 48,
+                flags: 0
+            })
+        );
+    }
+
+    #[test]
+    fn test_encrypted_content() {
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"more text"]);
+        for encryption in [Encryption::OldMobiPocket, Encryption::MobiPocket] {
+            m.metadata.palmdoc.encryption = encryption;
+            assert!(matches!(m.content_as_string(), Err(MobiError::Encrypted)));
+            assert!(matches!(m.try_content(), Err(MobiError::Encrypted)));
+            assert_eq!(m.content_as_string_lossy(), "");
+        }
+
+        m.metadata.palmdoc.encryption = Encryption::No;
+        assert_eq!(m.content_as_string().unwrap(), "textmore text");
+    }
+
+    #[test]
+    fn test_word_count() {
+        let m = book::mobi_with_records(&[
+            b"header",
+            b"<html><body><p>One ring to rule them all,</p>",
+            b"<p>one ring<br/>to find them</p></body></html>",
+        ]);
+        assert_eq!(m.word_count().unwrap(), 11);
+
+        let m = book::mobi_with_records(&[b"header"]);
+        assert_eq!(m.word_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_content_byte_chunks() {
+        let mut m = book::mobi_with_records(&[b"header", b"Hello ", b"\x02\xc5\xbc\xc3\xb3", b"!"]);
+        for compression in [Compression::No, Compression::PalmDoc] {
+            m.metadata.palmdoc.compression = compression;
+            let chunks = m
+                .content_byte_chunks()
+                .collect::<MobiResult<Vec<_>>>()
+                .unwrap();
+            assert_eq!(chunks.len(), 3);
+            assert_eq!(
+                String::from_utf8_lossy(&chunks.concat()),
+                m.content_as_string_lossy()
+            );
+        }
+
+        m.metadata.palmdoc.encryption = Encryption::MobiPocket;
+        let chunks: Vec<_> = m.content_byte_chunks().collect();
+        assert!(matches!(chunks[..], [Err(MobiError::Encrypted)]));
+    }
+
+    #[test]
+    fn test_open_lenient() {
+        let path = std::env::temp_dir().join("mobi-rs-test-open-lenient.mobi");
+
+        let mut bytes = book::full_book();
+        let exth_start = bytes.len() - book::BOOK.len();
+        bytes[exth_start..exth_start + 4].copy_from_slice(b"XXXX");
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert!(Mobi::from_path(&path).is_err());
+        let (mobi, warnings) = Mobi::open_lenient(&path).unwrap();
+        assert_eq!(mobi.metadata.exth, ExtHeader::default());
+        assert_eq!(mobi.title(), "Lord of the Rings - Fellowship of the Ring");
+        assert!(matches!(warnings[..], [ParseWarning::ExtHeader(_)]));
+
+        std::fs::write(&path, &bytes[..100]).unwrap();
+        assert!(matches!(
+            Mobi::open_lenient(&path),
+            Err(MobiError::MetadataParseError(_))
+        ));
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(
+            Mobi::open_lenient(&path),
+            Err(MobiError::IoError(_))
+        ));
+    }
+
+    #[test]
+    fn test_image_records_bounds() {
+        let mut m = book::mobi_with_records(&[
+            b"header",
+            b"text",
+            b"\xff\xd8\xff\xe0first",
+            b"\x89PNGsecond",
+            b"\x00\x00\x00\x01trailer",
+            b"\x00\x00\x00\x02trailer",
+        ]);
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_image_index = 2;
+
+        // No last content record, only FLIS and FCIS records are excluded
+        m.metadata.mobi.flis_record = 4;
+        m.metadata.mobi.fcis_record = 5;
+        let images: Vec<_> = m.image_records().iter().map(|r| r.content).collect();
+        assert_eq!(
+            images,
+            vec![&b"\xff\xd8\xff\xe0first"[..], b"\x89PNGsecond"]
+        );
+
+        m.metadata.mobi.flis_record = 0;
+        m.metadata.mobi.fcis_record = 0;
+        m.metadata.mobi.last_content_record = 3;
+        let images: Vec<_> = m.image_records().iter().map(|r| r.content).collect();
+        assert_eq!(
+            images,
+            vec![&b"\xff\xd8\xff\xe0first"[..], b"\x89PNGsecond"]
+        );
+    }
+
+    #[test]
+    fn test_find_text() {
+        let m = book::mobi_with_records(&[
+            b"header",
+            b"<p>One ring to rule them all, one ring to find them,</p>",
+            b"<p>One ring to bring them all and in the darkness bind them</p>",
+        ]);
+        assert_eq!(
+            m.find_text("ring").unwrap(),
+            vec![
+                TextMatch {
+                    record_index: 1,
+                    byte_offset: 7
+                },
+                TextMatch {
+                    record_index: 1,
+                    byte_offset: 34
+                },
+                TextMatch {
+                    record_index: 2,
+                    byte_offset: 7
+                },
+                TextMatch {
+                    record_index: 2,
+                    byte_offset: 16
+                },
+            ]
+        );
+        assert!(m.find_text("Mordor").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_content_as_html() {
+        let m = Mobi::new(book::full_book()).unwrap();
+        assert_eq!(m.content_as_html().unwrap(), m.content_as_string().unwrap());
+
+        let m = book::mobi_with_records(&[b"header", b"<p>text</p>\r\n", b"<p>\x07</p>"]);
+        assert!(matches!(
+            m.content_as_html(),
+            Err(MobiError::ControlCharacter(16))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_filepos() {
+        let m = book::mobi_with_records(&[b"header", b"first", b"", b"second", b"third"]);
+        assert_eq!(m.resolve_filepos(0), Some((1, 0)));
+        assert_eq!(m.resolve_filepos(4), Some((1, 4)));
+        assert_eq!(m.resolve_filepos(5), Some((3, 0)));
+        assert_eq!(m.resolve_filepos(13), Some((4, 2)));
+        assert_eq!(m.resolve_filepos(16), None);
+    }
+
+    #[test]
+    fn test_record_text_offsets() {
+        let m = book::mobi_with_records(&[b"header", b"first", b"", b"second", b"third"]);
+        let offsets = m.record_text_offsets().unwrap();
+        assert_eq!(offsets, vec![0, 5, 5, 11]);
+        assert!(offsets.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_content_encoding_is_valid() {
+        let mut m = book::mobi_with_records(&[b"header", b"caf\xe9", b"text"]);
+        m.metadata.mobi.text_encoding = TextEncoding::CP1252;
+        assert!(m.content_encoding_is_valid());
+
+        m.metadata.mobi.text_encoding = TextEncoding::UTF8;
+        assert!(!m.content_encoding_is_valid());
+
+        let mut m = book::mobi_with_records(&[b"header", b"text"]);
+        m.metadata.palmdoc.encryption = Encryption::MobiPocket;
+        assert!(!m.content_encoding_is_valid());
+
+        m.metadata.palmdoc.encryption = Encryption::No;
+        m.metadata.palmdoc.compression = Compression::Huff;
+        assert!(!m.content_encoding_is_valid());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn test_from_async_read() {
+        let bytes = book::full_book();
+        let reader = tokio::io::BufReader::new(bytes.as_slice());
+        let mobi = Mobi::from_async_read(reader).await.unwrap();
+
+        let expected = Mobi::new(&bytes).unwrap();
+        assert_eq!(mobi.metadata, expected.metadata);
+        assert_eq!(
+            mobi.content_as_string_lossy(),
+            expected.content_as_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_image_by_resource_index() {
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"\xFF\xD8\xFF1", b"GIF892"]);
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_image_index = 2;
+
+        assert_eq!(m.image_by_resource_index(1), Some(&b"\xFF\xD8\xFF1"[..]));
+        assert_eq!(m.image_by_resource_index(2), Some(&b"GIF892"[..]));
+        assert_eq!(m.image_by_resource_index(0), None);
+        assert_eq!(m.image_by_resource_index(3), None);
+        assert_eq!(m.image_by_resource_index(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_total_bytes() {
+        let m = Mobi::new(book::full_book()).unwrap();
+        assert!(m.total_image_bytes() + m.total_text_bytes() <= m.content.len());
+
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"more text", b"GIF89a"]);
+        m.metadata.mobi.first_non_book_index = 3;
+        m.metadata.mobi.first_image_index = 3;
+        assert_eq!(m.total_text_bytes(), 13);
+        assert_eq!(m.total_image_bytes(), 6);
+    }
+
+    #[test]
+    fn test_content_slice() {
+        let mut m = book::mobi_with_records(&[b"header", b"one ", b"two ", b"three", b"GIF89a"]);
+        m.metadata.mobi.first_non_book_index = 4;
+        m.metadata.mobi.first_image_index = 4;
+
+        assert_eq!(m.content_slice(1..3).unwrap(), b"one two ");
+
+        assert_eq!(m.content_slice(0..usize::MAX).unwrap(), b"one two three");
+        assert_eq!(m.content_slice(3..10).unwrap(), b"three");
+        assert!(m.content_slice(4..5).unwrap().is_empty());
+        #[allow(clippy::reversed_empty_ranges)]
+        let inverted = 3..1;
+        assert!(m.content_slice(inverted).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_from_reader_with_limits() {
+        let limits = ParseLimits {
+            max_records: 300,
+            max_record_size: 1 << 20,
+        };
+        let mobi = Mobi::from_reader_with_limits(book::full_book().as_slice(), limits).unwrap();
+        assert_eq!(
+            mobi.metadata,
+            Mobi::new(book::full_book()).unwrap().metadata
+        );
+
+        let mut bytes = book::full_book();
+        // Offset of the last record making the one before it absurdly large
+        bytes[2406..2410].copy_from_slice(&0xFFFF_FFF0u32.to_be_bytes());
+        assert!(matches!(
+            Mobi::from_reader_with_limits(bytes.as_slice(), limits),
+            Err(MobiError::RecordTooLarge(290, _))
+        ));
+        assert!(Mobi::from_reader_with_limits(bytes.as_slice(), ParseLimits::default()).is_ok());
+
+        let limits = ParseLimits {
+            max_records: 100,
+            ..limits
+        };
+        assert!(matches!(
+            Mobi::from_reader_with_limits(book::full_book().as_slice(), limits),
+            Err(MobiError::TooManyRecords(292))
+        ));
+    }
+
+    #[test]
+    fn test_from_reader_with_limits_stops_reading() {
+        let limits = ParseLimits {
+            max_records: 300,
+            max_record_size: 1 << 20,
+        };
+        // Last record starts at 676350, past the end of the fixture
+        let max_len = 676350 + (1 << 20) + 1;
+        let mut bytes = book::full_book();
+        bytes.resize(4 * max_len, 0);
+        let mut reader = io::Cursor::new(bytes.as_slice());
+
+        assert!(matches!(
+            Mobi::from_reader_with_limits(&mut reader, limits),
+            Err(MobiError::RecordTooLarge(291, _))
+        ));
+        assert_eq!(reader.position(), max_len as u64);
+    }
+
+    #[test]
+    fn test_from_reader_with_limits_huff_records() {
+        let limits = ParseLimits {
+            max_records: 300,
+            max_record_size: 1 << 20,
+        };
+        let mut bytes = book::full_book();
+        // HUFF record count in the MOBI header
+        bytes[2416 + 0x74..2416 + 0x78].copy_from_slice(&1000u32.to_be_bytes());
+        assert_eq!(
+            Mobi::new(&bytes).unwrap().metadata.mobi.huff_record_count,
+            1000
+        );
+        assert!(matches!(
+            Mobi::from_reader_with_limits(bytes.as_slice(), limits),
+            Err(MobiError::TooManyRecords(1000))
+        ));
+    }
+
+    #[test]
+    fn test_preview() {
+        let m = book::mobi_with_records(&[b"header", b"Three Rings ", b"for the Elven-kings"]);
+        let content = m.content_as_string().unwrap();
+
+        for max_chars in [0, 5, 12, 20, 100] {
+            let preview = m.preview(max_chars).unwrap();
+            assert!(preview.chars().count() <= max_chars);
+            assert!(content.starts_with(&preview));
+        }
+        assert_eq!(m.preview(15).unwrap(), "Three Rings for");
+        assert_eq!(m.preview(100).unwrap(), content);
+    }
+
+    #[test]
+    fn test_source_archive() {
+        let m = book::mobi_with_records(&[b"header", b"text"]);
+        assert_eq!(m.source_archive(), None);
+
+        let m = book::mobi_with_records(&[
+            b"header",
+            b"text",
+            b"SRCS\0\0\0\x10\0\0\0\x07\0\0\0\x01PK\x03\x04zip",
+        ]);
+        assert_eq!(m.source_archive(), Some(&b"PK\x03\x04zip"[..]));
+    }
+
+    #[test]
+    fn test_palmdoc_book() {
+        let m = Mobi::new(book::palmdoc_book(&[b"Caf\xe9 ", b"au lait"])).unwrap();
+        assert_eq!(m.content_as_string().unwrap(), "Café au lait");
+
+        assert_eq!(&m.metadata.header.type_, b"TEXt");
+        assert_eq!(&m.metadata.header.creator, b"REAd");
+
+        // Upper case identifiers are still accepted
+        let mut bytes = book::palmdoc_book(&[b"text"]);
+        bytes[60..68].copy_from_slice(b"TEXTREAD");
+        assert_eq!(
+            Mobi::new(&bytes).unwrap().content_as_string().unwrap(),
+            "text"
+        );
+    }
+
+    #[test]
+    fn test_records_checksum() {
+        let checksum = Mobi::new(book::full_book()).unwrap().records_checksum();
+        assert_eq!(
+            checksum,
+            Mobi::new(book::full_book()).unwrap().records_checksum()
+        );
+
+        let m = book::mobi_with_records(&[b"1234", b"56789"]);
+        assert_eq!(m.records_checksum(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_embedded_fonts() {
+        let mut m = book::mobi_with_records(&[
+            b"header",
+            b"text",
+            b"\x00\x01\x00\x00ttf",
+            b"GIF89a",
+            b"OTTOotf",
+            b"wOFFwoff",
+        ]);
+        m.metadata.mobi.first_image_index = 2;
+        assert_eq!(
+            m.embedded_fonts(),
+            vec![&b"\x00\x01\x00\x00ttf"[..], b"OTTOotf", b"wOFFwoff"]
+        );
+        assert_eq!(m.image_records().len(), 1);
+    }
+
+    #[test]
+    fn test_deobfuscate_font() {
+        let mut m = book::mobi_with_records(&[b"header"]);
+        let font = vec![0xAA; OBFUSCATED_FONT_LEN + 2];
+        assert_eq!(m.deobfuscate_font(&font), None);
+
+        m.metadata
+            .exth
+            .records
+            .insert(ExthRecord::FontSignature, vec![vec![0xAA, 0x55]]);
+        let deobfuscated = m.deobfuscate_font(&font).unwrap();
+        assert_eq!(&deobfuscated[..4], &[0x00, 0xFF, 0x00, 0xFF]);
+        assert_eq!(&deobfuscated[OBFUSCATED_FONT_LEN..], &[0xAA, 0xAA]);
+        assert_eq!(m.deobfuscate_font(&deobfuscated).unwrap(), font);
+    }
+
+    #[test]
+    fn test_is_empty_content() {
+        assert!(!book::mobi_with_records(&[b"header", b"text"]).is_empty_content());
+        // Fixture is truncated right after the headers record
+        assert!(Mobi::new(book::full_book()).unwrap().is_empty_content());
+        assert!(book::mobi_with_records(&[b"header"]).is_empty_content());
+        assert!(book::mobi_with_records(&[b"header", b"", b""]).is_empty_content());
+        assert!(!book::mobi_with_records(&[b"header", b"", b"text"]).is_empty_content());
+    }
+
+    #[test]
+    fn test_reencode_to_utf8() {
+        let cp1252 = b"Caf\xE9 na\xEFve ".repeat(350);
+        let mut m = book::mobi_with_records(&[
+            b"header",
+            &compression::palmdoc::compress(&cp1252),
+            b"GIF89a",
+        ]);
+        m.metadata.mobi.text_encoding = TextEncoding::CP1252;
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_image_index = 2;
+        m.metadata.palmdoc.compression = Compression::PalmDoc;
+        let text = m.content_as_string().unwrap();
+        assert_eq!(text, "Café naïve ".repeat(350));
+
+        m.reencode_to_utf8().unwrap();
+        assert_eq!(m.text_encoding(), TextEncoding::UTF8);
+        assert_eq!(m.compression(), Compression::PalmDoc);
+        assert_eq!(m.readable_records_range(), 1..3);
+        assert_eq!(m.metadata.palmdoc.record_count, 2);
+        assert_eq!(m.metadata.palmdoc.text_length as usize, text.len());
+        assert_eq!(m.content_as_string().unwrap(), text);
+        assert_eq!(m.image_records()[0].content, b"GIF89a");
+
+        let before = m.content.clone();
+        m.reencode_to_utf8().unwrap();
+        assert_eq!(m.content, before);
+    }
+
+    #[test]
+    fn test_reencode_to_utf8_text_only_book() {
+        let mut m = text_only_book(&[b"Caf\xE9 ", b"na\xEFve"]);
+        m.metadata.mobi.text_encoding = TextEncoding::CP1252;
+        assert_eq!(m.metadata.mobi.last_content_record, 2);
+
+        m.reencode_to_utf8().unwrap();
+        let m = written(&m);
+        assert_eq!(m.content_as_string().unwrap(), "Café naïve");
+        assert_eq!(m.readable_records_range(), 1..2);
+        assert_eq!(m.metadata.mobi.last_content_record, 1);
+        assert_eq!(m.metadata.records.num_records(), 5);
+        assert_eq!(contents(&m, m.metadata.mobi.flis_record as usize), b"FLIS");
+        assert_eq!(contents(&m, m.metadata.mobi.fcis_record as usize), b"FCIS");
+    }
+
+    #[test]
+    fn test_pages() {
+        let m = book::mobi_with_records(&[
+            b"header",
+            b"<p>Chapter 1</p><mbp:pagebreak/><p>Chap",
+            b"ter 2</p><mbp:pagebreak/><p>Chapter 3</p>",
+        ]);
+        assert_eq!(
+            m.pages().unwrap(),
+            vec![
+                "<p>Chapter 1</p>",
+                "<mbp:pagebreak/><p>Chapter 2</p>",
+                "<mbp:pagebreak/><p>Chapter 3</p>"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_guide_references() {
+        let m = book::mobi_with_records(&[
+            b"header",
+            b"<html><head><guide><reference type=\"toc\" title=\"Table of Contents\" \
+            filepos=0000200 /><reference type=\"start\" filepos=0000350 />\
+            <reference title=\"No type\" filepos=0000400 /></guide></head>\
+            <body><reference type=\"text\" filepos=0000500 /></body></html>",
+        ]);
+        assert_eq!(
+            m.guide_references().unwrap(),
+            vec![
+                GuideReference {
+                    ref_type: String::from("toc"),
+                    title: Some(String::from("Table of Contents")),
+                    filepos: 200,
+                },
+                GuideReference {
+                    ref_type: String::from("start"),
+                    title: None,
+                    filepos: 350,
+                },
+            ]
+        );
+        assert!(book::mobi_with_records(&[b"header", b"<p>text</p>"])
+            .guide_references()
+            .unwrap()
+            .is_empty());
+    }
+
+    fn written(m: &Mobi) -> Mobi {
+        let mut bytes = vec![];
+        m.write_to(&mut bytes).unwrap();
+        Mobi::new(bytes).unwrap()
+    }
+
+    fn contents(m: &Mobi, index: usize) -> Vec<u8> {
+        m.raw_records().records()[index].content.to_vec()
+    }
+
+    /// Builds a book without images out of the fixture headers, holding passed
+    /// uncompressed text records followed by FLIS, FCIS and EOF records.
+    fn text_only_book(text: &[&[u8]]) -> Mobi {
+        let mut m = Mobi::new(book::full_book()).unwrap();
+        let mut records: Vec<Vec<u8>> = vec![contents(&m, 0)];
+        records.extend(text.iter().map(|r| r.to_vec()));
+        records.extend([b"FLIS".to_vec(), b"FCIS".to_vec(), b"\xe9\x8e\r\n".to_vec()]);
+        m.set_records(&records);
+
+        let n = text.len() as u32;
+        let mobi = &mut m.metadata.mobi;
+        mobi.first_content_record = 1;
+        mobi.first_non_book_index = n + 1;
+        mobi.last_content_record = n as u16;
+        mobi.first_image_index = u32::MAX;
+        mobi.flis_record = n + 1;
+        mobi.fcis_record = n + 2;
+        mobi.extra_record_data_flags = 0;
+        let palmdoc = &mut m.metadata.palmdoc;
+        palmdoc.compression = Compression::No;
+        palmdoc.record_count = n as u16;
+        palmdoc.text_length = text.iter().map(|r| r.len() as u32).sum();
+        for record in [ExthRecord::CoverOffset, ExthRecord::ThumbOffset] {
+            m.metadata.exth.records.shift_remove(&record);
+        }
+        written(&m)
+    }
+
+    #[test]
+    fn test_write_to() {
+        let m = Mobi::new(book::full_book()).unwrap();
+        let w = written(&m);
+        assert_eq!(w.metadata.mobi, m.metadata.mobi);
+        assert_eq!(w.metadata.exth, m.metadata.exth);
+        assert_eq!(w.metadata.name, m.metadata.name);
+        assert_eq!(w.metadata.records.num_records(), 292);
+        assert_eq!(contents(&w, 1), contents(&m, 1));
+        assert_eq!(w.content_as_string_lossy(), m.content_as_string_lossy());
+    }
+
+    #[test]
+    fn test_replace_cover() {
+        let mut m = Mobi::new(book::full_book()).unwrap();
+        let cover = m.metadata.cover_record_index().unwrap();
+        m.replace_cover(b"\xFF\xD8\xFFcover");
+        let m = written(&m);
+        assert_eq!(m.metadata.cover_record_index(), Some(cover));
+        assert_eq!(contents(&m, cover), b"\xFF\xD8\xFFcover");
+        assert_eq!(m.metadata.records.num_records(), 292);
+    }
+
+    #[test]
+    fn test_strip_images() {
+        let mut m = Mobi::new(book::full_book()).unwrap();
+        m.replace_cover(b"\xFF\xD8\xFFcover");
+        assert_eq!(m.image_records().len(), 1);
+        let text = m.content_as_string().unwrap();
+
+        m.strip_images();
+        let m = written(&m);
+        assert!(m.image_records().is_empty());
+        assert_eq!(m.metadata.cover_record_index(), None);
+        assert_eq!(m.metadata.thumbnail_record_index(), None);
+        assert_eq!(m.metadata.records.num_records(), 291);
+        assert_eq!(m.content_as_string().unwrap(), text);
+    }
+
+    #[test]
+    fn test_replace_missing_cover() {
+        let mut m = Mobi::new(book::full_book()).unwrap();
+        m.metadata
+            .exth
+            .records
+            .shift_remove(&ExthRecord::CoverOffset);
+        let mobi = m.metadata.mobi.clone();
+        m.replace_cover(b"GIF89a");
+        let m = written(&m);
+        let cover = usize::from(mobi.last_content_record) + 1;
+        assert_eq!(m.metadata.records.num_records(), 293);
+        assert_eq!(m.metadata.cover_record_index(), Some(cover));
+        assert_eq!(contents(&m, cover), b"GIF89a");
+        assert_eq!(m.image_records()[0].content, b"GIF89a");
+        assert_eq!(m.metadata.mobi.first_image_index, mobi.first_image_index);
+        assert_eq!(m.metadata.mobi.flis_record, mobi.flis_record + 1);
+        assert_eq!(m.metadata.mobi.fcis_record, mobi.fcis_record + 1);
+    }
+
+    #[test]
+    fn test_replace_cover_text_only_book() {
+        let mut m = text_only_book(&[b"first ", b"second"]);
+        assert_eq!(m.content_as_string().unwrap(), "first second");
+        assert!(m.image_records().is_empty());
+
+        m.replace_cover(b"GIF89a");
+        let m = written(&m);
+        assert_eq!(m.content_as_string().unwrap(), "first second");
+        assert_eq!(m.readable_records_range(), 1..3);
+        assert_eq!(m.metadata.cover_record_index(), Some(3));
+        assert_eq!(contents(&m, 3), b"GIF89a");
+        assert_eq!(m.image_records()[0].content, b"GIF89a");
+        assert_eq!(m.metadata.mobi.last_content_record, 3);
+        assert_eq!(contents(&m, m.metadata.mobi.flis_record as usize), b"FLIS");
+        assert_eq!(contents(&m, m.metadata.mobi.fcis_record as usize), b"FCIS");
+    }
+
+    #[test]
+    fn test_uncompressed_text_length() {
+        let mobi = Mobi::new(book::full_book()).unwrap();
+        assert_eq!(mobi.uncompressed_text_length(), 1151461);
+    }
+
+    #[test]
+    fn test_classified_records() {
+        let mobi = Mobi::new(book::full_book()).unwrap();
+        let kinds: Vec<_> = mobi.classified_records().map(|(kind, _)| kind).collect();
+        assert_eq!(kinds.len(), 292);
+        assert_eq!(kinds[0], RecordKind::Other);
+        assert!(kinds[1..284].iter().all(|kind| *kind == RecordKind::Text));
+
+        let mut m = book::mobi_with_records(&[
+            b"header",
+            b"text",
+            b"\xff\xd8\xff\xe0image",
+            b"HUFF",
+            b"CDIC",
+            b"FLIS",
+            b"FCIS",
+            b"INDX",
+            b"\xe9\x8e\r\n",
+        ]);
+        m.metadata.mobi.first_non_book_index = 2;
+        m.metadata.mobi.first_image_index = 2;
+        let kinds: Vec<_> = m.classified_records().map(|(kind, _)| kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                RecordKind::Other,
+                RecordKind::Text,
+                RecordKind::Image,
+                RecordKind::Huff,
+                RecordKind::Cdic,
+                RecordKind::Flis,
+                RecordKind::Fcis,
+                RecordKind::Index,
+                RecordKind::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_content_truncated_records() {
+        let mut m = book::mobi_with_records(&[b"header", b"first", b"second", b"third"]);
+        m.content.truncate(8);
+        assert_eq!(m.try_content().unwrap(), "fi");
+
+        m.metadata.mobi.first_content_record = 3;
+        m.metadata.mobi.first_non_book_index = 1;
+        assert_eq!(m.try_content().unwrap(), "");
+
+        m.metadata.mobi.first_content_record = u16::MAX;
+        m.metadata.mobi.first_non_book_index = 0;
+        m.metadata.mobi.first_image_index = u32::MAX;
+        assert_eq!(m.try_content().unwrap(), "");
+        assert!(m.image_records().is_empty());
+    }
+
+    #[test]
+    fn test_try_content_corrupt_huff() {
+        let mut m = book::mobi_with_records(&[b"header", b"text", b"HUFF\0\0\0\x01", b"CDIC"]);
+        m.metadata.palmdoc.compression = Compression::Huff;
+        m.metadata.mobi.first_huff_record = 2;
+        m.metadata.mobi.huff_record_count = u32::MAX;
+        assert!(m.try_content().is_err());
+    }
+
     #[test]
     fn test_no_records() {
         let bytes = [
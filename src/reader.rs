@@ -29,8 +29,16 @@ impl<R: Read> Reader<R> {
         Ok(())
     }
 
+    /// Same as `read_to_end` but reads at most `limit` bytes.
+    pub(crate) fn read_to_end_limited(&mut self, buf: &mut Vec<u8>, limit: u64) -> io::Result<()> {
+        self.position += self.reader.by_ref().take(limit).read_to_end(buf)?;
+        Ok(())
+    }
+
     pub(crate) fn read_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
-        self.reader.read_exact(buf)?;
+        self.reader
+            .read_exact(buf)
+            .map_err(|e| self.with_position(e))?;
         self.position += buf.len();
         Ok(())
     }
@@ -46,17 +54,28 @@ impl<R: Read> Reader<R> {
             if copied_bytes != bytes_to_copy {
                 Err(io::Error::new(
                     io::ErrorKind::UnexpectedEof,
-                    "Tried to set cursor position past EOF",
+                    format!(
+                        "Tried to set cursor position {} past EOF at byte {}",
+                        p,
+                        self.position + copied_bytes as usize
+                    ),
                 ))
             } else {
                 self.position = p;
                 Ok(())
             }
         } else {
-            Err(io::Error::new(
-                io::ErrorKind::Other,
-                "can only seek position forwards",
-            ))
+            Err(io::Error::other("can only seek position forwards"))
+        }
+    }
+
+    /// Adds the current position to end of file errors so that it's known where
+    /// parsing of a truncated file stopped.
+    fn with_position(&self, e: io::Error) -> io::Error {
+        if e.kind() == io::ErrorKind::UnexpectedEof {
+            io::Error::new(e.kind(), format!("{} at byte {}", e, self.position))
+        } else {
+            e
         }
     }
 
@@ -99,8 +118,9 @@ impl<R: Read> Reader<R> {
             Err(io::Error::new(
                 io::ErrorKind::UnexpectedEof,
                 format!(
-                    "Tried to read {} byte header, only {} bytes available",
+                    "Tried to read {} byte header at byte {}, only {} bytes available",
                     len,
+                    self.position,
                     buf.len()
                 )
                 .as_str(),
@@ -111,3 +131,37 @@ impl<R: Read> Reader<R> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn position_advances() {
+        let mut reader = Reader::new(Cursor::new(vec![0; 20]));
+        reader.read_u32_be().unwrap();
+        assert_eq!(reader.position(), 4);
+        reader.read_u16_be().unwrap();
+        reader.read_u8().unwrap();
+        assert_eq!(reader.position(), 7);
+        reader.read_vec_header(5).unwrap();
+        assert_eq!(reader.position(), 12);
+        reader.set_position(16).unwrap();
+        reader.read_u64_be().unwrap_err();
+        assert_eq!(reader.position(), 16);
+    }
+
+    #[test]
+    fn truncation_errors_contain_position() {
+        let mut reader = Reader::new(Cursor::new(vec![0; 6]));
+        reader.read_u32_be().unwrap();
+        let e = reader.read_u32_be().unwrap_err();
+        assert_eq!(e.kind(), io::ErrorKind::UnexpectedEof);
+        assert!(e.to_string().ends_with("at byte 4"));
+        let e = reader.read_vec_header(8).unwrap_err();
+        assert!(e.to_string().contains("at byte 4"));
+        let e = reader.set_position(10).unwrap_err();
+        assert!(e.to_string().contains("position 10 past EOF"));
+    }
+}
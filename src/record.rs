@@ -10,6 +10,10 @@ use std::string::FromUtf8Error;
 use thiserror::Error;
 
 const EXTRA_BYTES_FLAG: u16 = 0xFFFE;
+/// Leading bytes of TrueType, OpenType and WOFF fonts
+const FONT_SIGNATURES: [&[u8]; 3] = [b"\x00\x01\x00\x00", b"OTTO", b"wOFF"];
+/// Length of the PDB header preceding the record list
+const PDB_HEADER_LEN: usize = 78;
 
 #[derive(Debug, Error)]
 pub enum PdbRecordParseError {
@@ -26,6 +30,22 @@ pub enum DecodeError {
     UTF8(FromUtf8Error),
     #[error("win-cp1252 decode failed - {0}")]
     CP1252(Cow<'static, str>),
+    #[cfg(feature = "full-encodings")]
+    #[error("codepage {0} decode failed")]
+    Codepage(u32),
+}
+
+#[derive(Debug, PartialEq, Eq, Copy, Clone)]
+/// Kind of a record decided from its position in the book and its magic bytes.
+pub enum RecordKind {
+    Text,
+    Image,
+    Huff,
+    Cdic,
+    Flis,
+    Fcis,
+    Index,
+    Other,
 }
 
 #[derive(Debug, Default, Copy, Clone)]
@@ -35,12 +55,49 @@ pub struct RawRecord<'a> {
 }
 
 impl<'a> RawRecord<'a> {
+    /// Returns the length of record content in bytes
+    pub fn len(&self) -> usize {
+        self.content.len()
+    }
+
+    /// Checks if record content is empty
+    pub fn is_empty(&self) -> bool {
+        self.content.is_empty()
+    }
+
+    /// Checks if this record is a HUFF record holding Huffman code tables
+    pub fn is_huff_record(&self) -> bool {
+        self.content.starts_with(b"HUFF")
+    }
+
+    /// Checks if this record is a CDIC record holding a Huffman dictionary
+    pub fn is_cdic_record(&self) -> bool {
+        self.content.starts_with(b"CDIC")
+    }
+
+    /// Returns content of this record if it's a HUFF record
+    pub fn as_huff_bytes(&self) -> Option<&'a [u8]> {
+        self.is_huff_record().then_some(self.content)
+    }
+
+    /// Returns content of this record if it's a CDIC record
+    pub fn as_cdic_bytes(&self) -> Option<&'a [u8]> {
+        self.is_cdic_record().then_some(self.content)
+    }
+
     pub(crate) fn decompress_palmdoc(&self) -> DecompressedRecord {
         DecompressedRecord(palmdoc::decompress(self.content))
     }
 
+    /// Checks if this record holds an embedded TrueType, OpenType or WOFF font
+    pub fn is_font_record(&self) -> bool {
+        FONT_SIGNATURES
+            .iter()
+            .any(|signature| self.content.starts_with(signature))
+    }
+
     pub(crate) fn is_image_record(&self) -> bool {
-        if self.content.len() < 4 {
+        if self.content.len() < 4 || self.is_font_record() {
             return false;
         }
         let bytes = &self.content[..4];
@@ -58,6 +115,12 @@ impl<'a> RawRecord<'a> {
     }
 }
 
+#[cfg(test)]
+thread_local! {
+    /// Number of times records were parsed on the current thread
+    pub(crate) static PARSE_COUNT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 #[derive(Debug, Default)]
 pub struct RawRecords<'a>(pub(crate) Vec<RawRecord<'a>>);
 
@@ -70,26 +133,37 @@ impl<'a> IntoIterator for RawRecords<'a> {
     }
 }
 
+impl<'a, 'b> IntoIterator for &'b RawRecords<'a> {
+    type Item = &'b RawRecord<'a>;
+    type IntoIter = std::slice::Iter<'b, RawRecord<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
 impl<'a> RawRecords<'a> {
     pub fn records(&self) -> &[RawRecord<'a>] {
         &self.0
     }
 
+    /// Returns records in passed range. Bounds past the end of records are clamped
+    /// and an inverted range yields no records.
     pub fn range(&self, range: impl RangeBounds<usize>) -> &[RawRecord<'a>] {
         let len = self.0.len();
-        if len == 0 {
-            return &[];
-        }
         let start = match range.start_bound() {
-            Bound::Excluded(b) | Bound::Included(b) => (*b).min(len - 1),
+            Bound::Included(b) => *b,
+            Bound::Excluded(b) => b.saturating_add(1),
             Bound::Unbounded => 0,
-        };
+        }
+        .min(len);
         let end = match range.end_bound() {
-            Bound::Excluded(b) => (*b - 1).min(len - 1),
-            Bound::Included(b) => (*b).min(len - 1),
+            Bound::Excluded(b) => *b,
+            Bound::Included(b) => b.saturating_add(1),
             Bound::Unbounded => len,
-        };
-        &self.0[start..end]
+        }
+        .min(len);
+        &self.0[start..end.max(start)]
     }
 }
 
@@ -100,6 +174,8 @@ pub struct PdbRecord {
 }
 
 #[derive(Debug, PartialEq, Eq, Default)]
+/// List of PDB records. This is the only records type, it is stored in
+/// `MobiMetadata::records` and used by `Mobi::raw_records` to slice the content.
 pub struct PdbRecords {
     pub records: Vec<PdbRecord>,
     extra_bytes: u16,
@@ -140,18 +216,36 @@ impl PdbRecords {
     }
 
     /// Parses content returing raw records that contain slices of content based on their offset.
+    /// Offsets pointing past the end of content are clamped, yielding empty records. Records
+    /// with an offset lower than a previous one, see `non_ascending_records`, are empty too.
+    /// No content is copied, records only borrow their slices of it.
     pub(crate) fn parse<'a>(&self, content: &'a [u8]) -> RawRecords<'a> {
-        let mut crecords = RawRecords::default();
+        #[cfg(test)]
+        PARSE_COUNT.with(|count| count.set(count.get() + 1));
+        RawRecords(self.iter_raw(content).collect())
+    }
+
+    /// Returns an iterator yielding raw records one by one, slicing content the same way
+    /// as `Mobi::raw_records` does but without collecting all of them up front.
+    pub fn iter_raw<'a, 'b: 'a>(
+        &'a self,
+        content: &'b [u8],
+    ) -> impl Iterator<Item = RawRecord<'b>> + 'a {
         let extra_bytes = self.extra_bytes as usize;
-        let mut records = self.records.iter().peekable();
+        let mut prev_offset = 0;
 
-        while let Some(record) = records.next() {
-            let curr_offset = record.offset as usize;
-            let content = if let Some(next) = records.peek() {
-                let next_offset = next.offset as usize;
+        self.records.iter().enumerate().map(move |(i, record)| {
+            let curr_offset = (record.offset as usize).min(content.len());
+            // Records going backwards would overlap previous records
+            let ascending = record.offset >= prev_offset;
+            prev_offset = prev_offset.max(record.offset);
+            let content = if !ascending {
+                &[]
+            } else if let Some(next) = self.records.get(i + 1) {
+                let next_offset = (next.offset as usize).min(content.len());
 
                 if extra_bytes < next_offset {
-                    &content[curr_offset..(next_offset - extra_bytes)]
+                    &content[curr_offset..(next_offset - extra_bytes).max(curr_offset)]
                 } else {
                     &[]
                 }
@@ -159,22 +253,65 @@ impl PdbRecords {
                 &content[curr_offset..]
             };
 
-            crecords.0.push(RawRecord {
+            RawRecord {
                 record: *record,
                 content,
-            });
-        }
-        crecords
+            }
+        })
     }
 
     pub fn extra_bytes(&self) -> u32 {
-        2 * (self.extra_bytes & EXTRA_BYTES_FLAG).count_ones() as u32
+        2 * (self.extra_bytes & EXTRA_BYTES_FLAG).count_ones()
     }
 
     pub fn num_records(&self) -> u16 {
         self.records.len() as u16
     }
 
+    /// Returns indices of records whose offset is lower than offset of any previous record.
+    /// Parsing rejects such records, but they can be introduced by editing `records`.
+    pub fn non_ascending_records(&self) -> Vec<usize> {
+        let mut prev_offset = 0;
+        self.records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| {
+                let ascending = record.offset >= prev_offset;
+                prev_offset = prev_offset.max(record.offset);
+                !ascending
+            })
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    /// Returns offsets of all records in the order they appear in the record list
+    pub fn offsets(&self) -> Vec<u32> {
+        self.records.iter().map(|record| record.offset).collect()
+    }
+
+    /// Returns the same records laid out one after another with passed sizes, the first
+    /// one starting right after the record list.
+    pub(crate) fn laid_out(&self, sizes: impl Iterator<Item = usize>) -> PdbRecords {
+        let mut offset = PDB_HEADER_LEN + 8 * self.records.len() + 2;
+        let records = self
+            .records
+            .iter()
+            .zip(sizes)
+            .map(|(record, size)| {
+                let record = PdbRecord {
+                    id: record.id,
+                    offset: offset as u32,
+                };
+                offset += size;
+                record
+            })
+            .collect();
+        PdbRecords {
+            records,
+            extra_bytes: self.extra_bytes,
+        }
+    }
+
     pub(crate) fn write<W: io::Write>(&self, w: &mut Writer<W>) -> io::Result<()> {
         for record in &self.records {
             w.write_be(record.offset)?;
@@ -184,7 +321,59 @@ impl PdbRecords {
     }
 }
 
+#[cfg(feature = "full-encodings")]
+/// Maps a Windows codepage number to a codec. Only encodings that are not natively
+/// supported by Mobi format are mapped.
+fn codepage_codec(encoding: TextEncoding) -> Option<&'static encoding_rs::Encoding> {
+    use encoding_rs::*;
+
+    let codepage = match encoding {
+        TextEncoding::Unknown(codepage) => codepage,
+        _ => return None,
+    };
+
+    Some(match codepage {
+        874 => WINDOWS_874,
+        932 => SHIFT_JIS,
+        936 => GBK,
+        949 => EUC_KR,
+        950 => BIG5,
+        1200 => UTF_16LE,
+        1201 => UTF_16BE,
+        1250 => WINDOWS_1250,
+        1251 => WINDOWS_1251,
+        1253 => WINDOWS_1253,
+        1254 => WINDOWS_1254,
+        1255 => WINDOWS_1255,
+        1256 => WINDOWS_1256,
+        1257 => WINDOWS_1257,
+        1258 => WINDOWS_1258,
+        20866 => KOI8_R,
+        20932 | 51932 => EUC_JP,
+        21866 => KOI8_U,
+        // Latin-1 is decoded as its superset windows-1252
+        28591 => WINDOWS_1252,
+        28592 => ISO_8859_2,
+        28593 => ISO_8859_3,
+        28594 => ISO_8859_4,
+        28595 => ISO_8859_5,
+        28596 => ISO_8859_6,
+        28597 => ISO_8859_7,
+        28598 => ISO_8859_8,
+        28603 => ISO_8859_13,
+        28605 => ISO_8859_15,
+        50220 => ISO_2022_JP,
+        54936 => GB18030,
+        _ => return None,
+    })
+}
+
 pub(crate) fn content_to_string_lossy(content: &[u8], encoding: TextEncoding) -> String {
+    #[cfg(feature = "full-encodings")]
+    if let Some(codec) = codepage_codec(encoding) {
+        return codec.decode_without_bom_handling(content).0.into_owned();
+    }
+
     match encoding {
         TextEncoding::UTF8 | TextEncoding::Unknown(_) => {
             String::from_utf8_lossy(content).into_owned()
@@ -197,6 +386,14 @@ pub(crate) fn content_to_string(
     content: &[u8],
     encoding: TextEncoding,
 ) -> Result<String, DecodeError> {
+    #[cfg(feature = "full-encodings")]
+    if let Some(codec) = codepage_codec(encoding) {
+        return codec
+            .decode_without_bom_handling_and_without_replacement(content)
+            .map(Cow::into_owned)
+            .ok_or_else(|| DecodeError::Codepage(encoding.into()));
+    }
+
     match encoding {
         TextEncoding::UTF8 | TextEncoding::Unknown(_) => {
             String::from_utf8(content.to_vec()).map_err(DecodeError::UTF8)
@@ -207,6 +404,38 @@ pub(crate) fn content_to_string(
     }
 }
 
+/// Updates a CRC-32 (IEEE) checksum with passed bytes. Checksum of concatenated
+/// data is computed by passing the result of previous call, starting with 0.
+pub(crate) fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &b in bytes {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+/// Reads a forward encoded variable width integer used by MOBI index records from the
+/// start of passed bytes. Every byte holds 7 bits of the value, most significant first,
+/// and the high bit is set on the last byte. Returns the value and the number of bytes
+/// it took, or `None` if the bytes end before the last byte or the value overflows u32.
+pub fn read_varint(bytes: &[u8]) -> Option<(u32, usize)> {
+    let mut value: u32 = 0;
+    // 5 bytes carry 35 bits which is enough for any u32
+    for (i, &byte) in bytes.iter().take(5).enumerate() {
+        if value.leading_zeros() < 7 {
+            return None;
+        }
+        value = (value << 7) | u32::from(byte & 0x7F);
+        if byte & 0x80 != 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
 #[derive(Debug, Default)]
 pub(crate) struct DecompressedRecord(pub Vec<u8>);
 
@@ -231,6 +460,139 @@ mod test {
         let _records = PdbRecords::new(&mut reader, 292).unwrap();
     }
 
+    #[test]
+    fn non_ascending_offsets() {
+        let mut records = PdbRecords::default();
+        for offset in [0, 4, 2, 6, 3, 8] {
+            records.records.push(PdbRecord { id: 0, offset });
+        }
+        assert_eq!(records.non_ascending_records(), vec![2, 4]);
+
+        // Records followed by a non ascending one are empty as well, they end before they start
+        let content = b"0123456789";
+        let parsed = records.parse(content);
+        let contents: Vec<&[u8]> = parsed.records().iter().map(|r| r.content).collect();
+        assert_eq!(contents, vec![&b"0123"[..], b"", b"", b"", b"", b"89"]);
+    }
+
+    #[test]
+    fn iter_raw() {
+        let mut reader = book::u8_reader(book::RECORDS.to_vec());
+        let records = PdbRecords::new(&mut reader, 292).unwrap();
+        let content = book::full_book();
+
+        let parsed = records.parse(&content);
+        let lazy: Vec<RawRecord> = records.iter_raw(&content).collect();
+        assert_eq!(lazy.len(), parsed.records().len());
+        for (lazy, parsed) in lazy.iter().zip(parsed.records()) {
+            assert_eq!(lazy.record, parsed.record);
+            assert_eq!(lazy.content, parsed.content);
+        }
+        assert_eq!(
+            records.iter_raw(&content).nth(1).unwrap().record.offset,
+            records.records[1].offset
+        );
+    }
+
+    #[test]
+    fn offsets() {
+        let mut reader = book::u8_reader(book::RECORDS.to_vec());
+        let offsets = PdbRecords::new(&mut reader, 292).unwrap().offsets();
+        assert_eq!(offsets.len(), 292);
+        assert_eq!(offsets[0], 2416);
+        assert!(offsets.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn raw_record_len() {
+        let record = RawRecord {
+            record: PdbRecord::default(),
+            content: b"content",
+        };
+        assert_eq!(record.len(), 7);
+        assert!(!record.is_empty());
+        assert!(RawRecord::default().is_empty());
+    }
+
+    #[test]
+    fn huff_cdic_records() {
+        let m =
+            book::mobi_with_records(&[b"header", b"HUFF\0\0\0\x18", b"CDIC\0\0\0\x10", b"text"]);
+        let records = m.raw_records();
+        let records = records.records();
+
+        assert!(records[1].is_huff_record());
+        assert!(!records[1].is_cdic_record());
+        assert_eq!(records[1].as_huff_bytes(), Some(&b"HUFF\0\0\0\x18"[..]));
+        assert_eq!(records[1].as_cdic_bytes(), None);
+
+        assert!(records[2].is_cdic_record());
+        assert_eq!(records[2].as_cdic_bytes(), Some(&b"CDIC\0\0\0\x10"[..]));
+        assert_eq!(records[2].as_huff_bytes(), None);
+
+        assert!(!records[3].is_huff_record() && !records[3].is_cdic_record());
+    }
+
+    #[test]
+    fn range() {
+        let records = RawRecords(
+            (0..5)
+                .map(|offset| RawRecord {
+                    record: PdbRecord { id: 0, offset },
+                    content: &[],
+                })
+                .collect(),
+        );
+        let offsets = |records: &[RawRecord]| -> Vec<u32> {
+            records.iter().map(|r| r.record.offset).collect()
+        };
+
+        assert_eq!(offsets(records.range(2..)), vec![2, 3, 4]);
+        assert_eq!(offsets(records.range(..)), vec![0, 1, 2, 3, 4]);
+        assert_eq!(offsets(records.range(1..3)), vec![1, 2]);
+        assert_eq!(offsets(records.range(1..=3)), vec![1, 2, 3]);
+        assert_eq!(offsets(records.range(3..10)), vec![3, 4]);
+        assert!(records.range(5..).is_empty());
+        assert!(records
+            .range((Bound::Included(4), Bound::Excluded(2)))
+            .is_empty());
+        assert!(records.range(..0).is_empty());
+        assert!(RawRecords::default().range(..).is_empty());
+    }
+
+    #[test]
+    fn iter_by_ref() {
+        let records = RawRecords(
+            (0..3)
+                .map(|offset| RawRecord {
+                    record: PdbRecord { id: 0, offset },
+                    content: &[],
+                })
+                .collect(),
+        );
+
+        let mut offsets = vec![];
+        for record in &records {
+            offsets.push(record.record.offset);
+        }
+        assert_eq!(offsets, vec![0, 1, 2]);
+        assert_eq!(records.records().len(), 3);
+    }
+
+    #[test]
+    fn crc32_checksum() {
+        assert_eq!(crc32(0, b""), 0);
+        assert_eq!(crc32(0, b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn metadata_records() {
+        let m = crate::MobiMetadata::new(book::full_book()).unwrap();
+        let records: &crate::record::PdbRecords = &m.records;
+        assert_eq!(records.num_records(), 292);
+    }
+
     #[test]
     fn test_write() {
         let records = book::RECORDS.to_vec();
@@ -241,4 +603,48 @@ mod test {
         assert_eq!(records.len(), written.len());
         assert_eq!(records, written);
     }
+
+    #[test]
+    #[cfg(feature = "full-encodings")]
+    fn decode_shift_jis() {
+        let content = b"\x93\xfa\x96\x7b\x8c\xea";
+        let encoding = TextEncoding::Unknown(932);
+
+        assert_eq!(content_to_string(content, encoding).unwrap(), "日本語");
+        assert_eq!(content_to_string_lossy(content, encoding), "日本語");
+        assert!(matches!(
+            content_to_string(b"\x93", encoding),
+            Err(DecodeError::Codepage(932))
+        ));
+    }
+
+    #[test]
+    fn varint_single_byte() {
+        assert_eq!(read_varint(&[0x80]), Some((0, 1)));
+        assert_eq!(read_varint(&[0x85]), Some((5, 1)));
+        assert_eq!(read_varint(&[0xFF, 0x00]), Some((0x7F, 1)));
+    }
+
+    #[test]
+    fn varint_multi_byte() {
+        assert_eq!(read_varint(&[0x01, 0x80]), Some((0x80, 2)));
+        assert_eq!(read_varint(&[0x04, 0x22, 0x91]), Some((0x11111, 3)));
+
+        let bytes = [0x01, 0xFF, 0x82];
+        let (first, len) = read_varint(&bytes).unwrap();
+        assert_eq!((first, len), (0xFF, 2));
+        assert_eq!(read_varint(&bytes[len..]), Some((2, 1)));
+    }
+
+    #[test]
+    fn varint_max() {
+        assert_eq!(
+            read_varint(&[0x0F, 0x7F, 0x7F, 0x7F, 0xFF]),
+            Some((u32::MAX, 5))
+        );
+        assert_eq!(read_varint(&[0x1F, 0x7F, 0x7F, 0x7F, 0xFF]), None);
+        assert_eq!(read_varint(&[0x00, 0x00, 0x00, 0x00, 0x00, 0x80]), None);
+        assert_eq!(read_varint(&[0x01]), None);
+        assert_eq!(read_varint(&[]), None);
+    }
 }
@@ -69,10 +69,6 @@ impl<W: std::io::Write> Writer<W> {
         }
     }
 
-    pub(crate) fn bytes_written(&self) -> usize {
-        self.bytes_written
-    }
-
     #[inline]
     pub(crate) fn write_be<B: WriteBeBytes>(&mut self, b: B) -> io::Result<()> {
         self.bytes_written += b.write_be_bytes(&mut self.writer)?;